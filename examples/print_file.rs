@@ -1,18 +1,21 @@
-use std::{error::Error, path::PathBuf};
+use std::{error::Error, fs::File, io, path::PathBuf};
 
-use fast_dlt::file::DltFile;
+use fast_dlt::DltReader;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let Some(path) = std::env::args().nth(1).map(PathBuf::from) else {
-        return Err("This example expects a path to a DLT file!".into());
+    // read from stdin if no path is given, so it can be used in a pipe
+    let input: Box<dyn io::Read> = match std::env::args().nth(1).map(PathBuf::from) {
+        Some(path) => Box::new(File::open(path)?),
+        None => Box::new(io::stdin().lock()),
     };
 
-    let data = std::fs::read(path)?;
+    let mut reader = DltReader::new(input);
 
-    let file = DltFile::new(&data);
-
-    file.flat_map(Result::ok)
-        .for_each(|message| println!("{message}"));
+    while let Some(message) = reader.next_message() {
+        if let Ok(message) = message {
+            println!("{message}");
+        }
+    }
 
     Ok(())
 }
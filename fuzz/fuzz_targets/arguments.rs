@@ -0,0 +1,20 @@
+#![no_main]
+
+use fast_dlt::payload::{StringDecoding, VerbosePayload};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&flags, data)) = data.split_first() else {
+        return;
+    };
+
+    let mut payload = VerbosePayload::new(data, flags & 1 != 0);
+    if flags & 2 != 0 {
+        payload.set_string_decoding(StringDecoding::Lossy);
+    }
+    for argument in payload.arguments().flatten() {
+        let _ = argument.to_string();
+        let _ = argument.physical_value();
+    }
+    let _ = payload.to_string();
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use fast_dlt::{DltFile, DltStreamFile};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    for message in DltFile::new(data)
+        .flatten()
+        .chain(DltStreamFile::new(data).flatten())
+    {
+        let _ = message.to_string();
+        if let Some(payload) = message.payload.as_verbose() {
+            for argument in payload.arguments().flatten() {
+                let _ = argument.to_string();
+            }
+        }
+    }
+});
@@ -0,0 +1,29 @@
+#![no_main]
+
+use fast_dlt::parser::{DltParser, Framing, Parsed};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // the first byte decides the framing and how the rest is split into chunks
+    let Some((&control, data)) = data.split_first() else {
+        return;
+    };
+    let framing = match control % 3 {
+        0 => Framing::StorageHeader,
+        1 => Framing::Raw,
+        _ => Framing::Serial,
+    };
+    let mut parser = DltParser::new(framing);
+    for chunk in data.chunks(control as usize / 3 + 1) {
+        parser.feed(chunk);
+        loop {
+            match parser.poll() {
+                Parsed::Message(message) => {
+                    let _ = message.to_string();
+                }
+                Parsed::Error(_) => {}
+                Parsed::Incomplete { .. } => break,
+            }
+        }
+    }
+});
@@ -0,0 +1,354 @@
+//! Descriptions of non-verbose messages.
+//!
+//! A non-verbose payload only carries a message id and the raw values of its arguments,
+//! their types and any static text are described in a separate file. A [`Catalog`] holds these
+//! descriptions and turns non-verbose payloads into the same [`Argument`]s as verbose payloads.
+
+use std::collections::HashMap;
+
+use crate::{
+    error::ParseError,
+    header::MessageTypeInfo,
+    message::DltMessage,
+    payload::{Argument, NonVerbosePayload, StringDecoding, TypeInfo},
+};
+
+#[cfg(feature = "arxml")]
+pub mod arxml;
+#[cfg(feature = "fibex")]
+pub mod fibex;
+#[cfg(any(feature = "fibex", feature = "arxml"))]
+mod xml;
+
+/// The descriptions of non-verbose messages, by ECU and message id
+#[derive(Debug, Default, Clone)]
+pub struct Catalog {
+    frames: HashMap<(String, u32), Vec<Frame>>,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the description of a message sent by the ECU `ecu_id`
+    pub fn insert(&mut self, ecu_id: &str, frame: Frame) {
+        self.frames
+            .entry((ecu_id.to_string(), frame.message_id))
+            .or_default()
+            .push(frame);
+    }
+
+    /// Adds all descriptions of `other`
+    pub fn extend(&mut self, other: Catalog) {
+        for (key, frames) in other.frames {
+            self.frames.entry(key).or_default().extend(frames);
+        }
+    }
+
+    /// Looks up the description of a message.
+    ///
+    /// Message ids only have to be unique per application and context, so the description with
+    /// the given application and context id is preferred, followed by one that is not limited
+    /// to a specific application or context. A message without these ids, i.e. without extended header,
+    /// only gets a description that is limited to one if it is the only one with this message id.
+    pub fn frame(
+        &self,
+        ecu_id: &str,
+        application_id: Option<&str>,
+        context_id: Option<&str>,
+        message_id: u32,
+    ) -> Option<&Frame> {
+        let frames = self.frames.get(&(ecu_id.to_string(), message_id))?;
+        let matches =
+            |frame_id: &Option<String>, id| frame_id.is_none() || frame_id.as_deref() == id;
+        frames
+            .iter()
+            .find(|frame| {
+                frame.application_id.as_deref() == application_id
+                    && frame.context_id.as_deref() == context_id
+            })
+            .or_else(|| {
+                frames.iter().find(|frame| {
+                    matches(&frame.application_id, application_id)
+                        && matches(&frame.context_id, context_id)
+                })
+            })
+            .or_else(|| match frames.as_slice() {
+                [frame] if application_id.is_none() && context_id.is_none() => Some(frame),
+                _ => None,
+            })
+    }
+
+    /// Decodes the arguments of a non-verbose message, with the description of the ECU that sent it,
+    /// see [`DltMessage::sender_ecu_id`].
+    /// Returns `None` if the message is verbose or there is no description for it.
+    pub fn decode<'a>(
+        &'a self,
+        message: &DltMessage<'a>,
+    ) -> Option<Result<Vec<Argument<'a>>, ParseError>> {
+        let payload = message.payload.as_non_verbose()?;
+        let frame = self.frame(
+            message.sender_ecu_id(),
+            message.app_id(),
+            message.context_id(),
+            payload.message_id(),
+        )?;
+        Some(frame.decode(
+            payload,
+            message.standard_header.msb_first(),
+            payload.string_decoding(),
+        ))
+    }
+
+    /// The number of described messages
+    pub fn len(&self) -> usize {
+        self.frames.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+/// The description of a non-verbose message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub message_id: u32,
+    pub application_id: Option<String>,
+    pub context_id: Option<String>,
+    pub type_info: Option<MessageTypeInfo>,
+    pub parts: Vec<FramePart>,
+}
+
+impl Frame {
+    /// Decodes the arguments of `payload`, with the static text as string arguments in between
+    pub fn decode<'a>(
+        &'a self,
+        payload: &NonVerbosePayload<'a>,
+        msb_first: bool,
+        string_decoding: StringDecoding,
+    ) -> Result<Vec<Argument<'a>>, ParseError> {
+        let mut data = payload.data();
+        self.parts
+            .iter()
+            .map(|part| match part {
+                FramePart::Text(text) => Ok(Argument::text(text)),
+                FramePart::Signal(signal) => {
+                    let argument = Argument::with_type_info(
+                        signal.type_info(),
+                        data,
+                        msb_first,
+                        string_decoding,
+                        0,
+                    )?;
+                    data = &data[argument.len()..];
+                    Ok(argument)
+                }
+            })
+            .collect()
+    }
+}
+
+/// A piece of a non-verbose message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FramePart {
+    /// Static text, which is not part of the payload
+    Text(String),
+    /// A value in the payload
+    Signal(SignalType),
+}
+
+/// The type of a value in a non-verbose payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+    /// A string with a 16 bit length prefix
+    Ascii,
+    /// A string with a 16 bit length prefix
+    Utf8,
+    /// Raw data with a 16 bit length prefix
+    Raw,
+}
+
+impl SignalType {
+    /// The type info of a verbose argument with the same layout
+    fn type_info(self) -> u32 {
+        match self {
+            SignalType::Bool => TypeInfo::Bool as u32 | TypeInfo::Size8 as u32,
+            SignalType::U8 => TypeInfo::Unsigned as u32 | TypeInfo::Size8 as u32,
+            SignalType::U16 => TypeInfo::Unsigned as u32 | TypeInfo::Size16 as u32,
+            SignalType::U32 => TypeInfo::Unsigned as u32 | TypeInfo::Size32 as u32,
+            SignalType::U64 => TypeInfo::Unsigned as u32 | TypeInfo::Size64 as u32,
+            SignalType::I8 => TypeInfo::Signed as u32 | TypeInfo::Size8 as u32,
+            SignalType::I16 => TypeInfo::Signed as u32 | TypeInfo::Size16 as u32,
+            SignalType::I32 => TypeInfo::Signed as u32 | TypeInfo::Size32 as u32,
+            SignalType::I64 => TypeInfo::Signed as u32 | TypeInfo::Size64 as u32,
+            SignalType::F32 => TypeInfo::Float as u32 | TypeInfo::Size32 as u32,
+            SignalType::F64 => TypeInfo::Float as u32 | TypeInfo::Size64 as u32,
+            SignalType::Ascii => TypeInfo::String as u32 | TypeInfo::Ascii as u32,
+            SignalType::Utf8 => TypeInfo::String as u32 | TypeInfo::Utf8 as u32,
+            SignalType::Raw => TypeInfo::Raw as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::{header::LogInfo, DltFile};
+
+    #[test]
+    fn decode_frame() {
+        let frame = Frame {
+            message_id: 42,
+            application_id: None,
+            context_id: None,
+            type_info: Some(MessageTypeInfo::Log(LogInfo::Info)),
+            parts: vec![
+                FramePart::Text("temperature".to_string()),
+                FramePart::Signal(SignalType::I16),
+                FramePart::Text("at".to_string()),
+                FramePart::Signal(SignalType::Utf8),
+                FramePart::Signal(SignalType::Bool),
+            ],
+        };
+        let data = b"\x2a\x00\x00\x00\xf6\xff\x04\x00east\x01";
+        let payload = NonVerbosePayload::from_slice(data, data.len(), false).unwrap();
+        let arguments = frame
+            .decode(&payload, false, StringDecoding::Strict)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(arguments, ["temperature", "-10", "at", "east", "true"]);
+
+        let truncated = NonVerbosePayload::from_slice(&data[..8], 8, false).unwrap();
+        assert!(matches!(
+            frame.decode(&truncated, false, StringDecoding::Strict),
+            Err(ParseError::NotEnoughData { .. })
+        ));
+    }
+
+    #[test]
+    fn prefer_matching_application_and_context() {
+        let frame = |application_id: &str| Frame {
+            message_id: 1,
+            application_id: Some(application_id.to_string()),
+            context_id: Some("CTX".to_string()),
+            type_info: None,
+            parts: Vec::new(),
+        };
+        let mut catalog = Catalog::new();
+        catalog.insert("ECU", frame("APP1"));
+        catalog.insert("ECU", frame("APP2"));
+        assert_eq!(catalog.len(), 2);
+
+        let found = |application_id| {
+            catalog
+                .frame("ECU", application_id, Some("CTX"), 1)
+                .and_then(|frame| frame.application_id.as_deref())
+        };
+        assert_eq!(found(Some("APP2")), Some("APP2"));
+        assert_eq!(found(Some("APP1")), Some("APP1"));
+        assert!(catalog.frame("ECU2", None, None, 1).is_none());
+    }
+
+    #[test]
+    fn string_decoding_of_the_message() {
+        let mut catalog = Catalog::new();
+        catalog.insert(
+            "ECU",
+            Frame {
+                message_id: 1,
+                application_id: None,
+                context_id: None,
+                type_info: None,
+                parts: vec![FramePart::Signal(SignalType::Utf8)],
+            },
+        );
+        // a non-verbose message with the message id 1 and a string that is not valid UTF-8
+        let data = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                     \x21\x00\x00\x17\
+                     \x40\x00APP1CTX1\
+                     \x01\x00\x00\x00\x03\x00a\xff\x00";
+
+        let message = DltFile::new(data).next().unwrap().unwrap();
+        assert!(matches!(
+            catalog.decode(&message),
+            Some(Err(ParseError::BadUTF8(_)))
+        ));
+
+        let message = DltFile::new(data)
+            .with_string_decoding(StringDecoding::Lossy)
+            .next()
+            .unwrap()
+            .unwrap();
+        let arguments = catalog.decode(&message).unwrap().unwrap();
+        assert_eq!(arguments[0].to_string(), "a\u{fffd}");
+    }
+
+    #[test]
+    fn description_of_the_sender() {
+        let mut catalog = Catalog::new();
+        catalog.insert(
+            "ECU1",
+            Frame {
+                message_id: 1,
+                application_id: None,
+                context_id: None,
+                type_info: None,
+                parts: vec![FramePart::Signal(SignalType::U8)],
+            },
+        );
+        // stored by a logger with another ECU id than the one of the standard header
+        let data = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00LOG\x00\
+                     \x25\x00\x00\x17ECU1\
+                     \x40\x00APP1CTX1\
+                     \x01\x00\x00\x00\x2a";
+        let message = DltFile::new(data).next().unwrap().unwrap();
+        let arguments = catalog.decode(&message).unwrap().unwrap();
+        assert_eq!(arguments[0].to_string(), "42");
+    }
+
+    #[test]
+    fn no_description_of_other_applications() {
+        let frame = |application_id: Option<&str>, parts| Frame {
+            message_id: 1,
+            application_id: application_id.map(str::to_string),
+            context_id: None,
+            type_info: None,
+            parts,
+        };
+        let mut catalog = Catalog::new();
+        catalog.insert("ECU", frame(Some("APP1"), vec![]));
+        catalog.insert("ECU", frame(Some("APP2"), vec![]));
+        // the message ids of other applications, or of unknown ones, must not decode with their layout
+        assert!(catalog.frame("ECU", Some("APP3"), Some("CTX"), 1).is_none());
+        assert!(catalog.frame("ECU", None, None, 1).is_none());
+
+        let only = frame(Some("APP1"), vec![FramePart::Text("only".to_string())]);
+        catalog.insert("ECU2", only.clone());
+        assert_eq!(catalog.frame("ECU2", None, None, 1), Some(&only));
+        assert!(catalog.frame("ECU2", Some("APP2"), None, 1).is_none());
+
+        let any = frame(None, vec![FramePart::Text("any".to_string())]);
+        catalog.insert("ECU", any.clone());
+        assert_eq!(
+            catalog.frame("ECU", Some("APP3"), Some("CTX"), 1),
+            Some(&any)
+        );
+        assert_eq!(catalog.frame("ECU", None, None, 1), Some(&any));
+    }
+}
@@ -0,0 +1,347 @@
+//! Loading non-verbose message descriptions from AUTOSAR ARXML files.
+//!
+//! Every `DLT-MESSAGE` describes one message with its `MESSAGE-ID`, optional `MESSAGE-TYPE-INFO`
+//! and `DLT-ARGUMENTS`. An argument is either static text (`PREDEFINED-TEXT`) or a value, whose
+//! type follows from the `SW-BASE-TYPE` referenced in its `NETWORK-REPRESENTATION`.
+//!
+//! Which ECU, application and context send a message follows from the `DLT-CONTEXT` that either
+//! contains the message or references it with a `DLT-MESSAGE-REF`, inside of a `DLT-APPLICATION`
+//! and `DLT-ECU`. Messages that no context contains or references are skipped,
+//! since no ECU sends them.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use roxmltree::{Document, Node};
+
+use crate::{
+    catalog::{
+        xml::{child, child_text, type_info},
+        Catalog, Frame, FramePart, SignalType,
+    },
+    error::CatalogError,
+};
+
+/// Where a message comes from
+struct Sender<'a> {
+    ecu_id: &'a str,
+    application_id: Option<&'a str>,
+    context_id: Option<&'a str>,
+}
+
+impl Catalog {
+    /// Loads all messages described in the ARXML file at `path`
+    pub fn load_arxml(path: impl AsRef<Path>) -> Result<Self, CatalogError> {
+        Self::from_arxml(&fs::read_to_string(path)?)
+    }
+
+    /// Loads all messages described in an ARXML document
+    pub fn from_arxml(xml: &str) -> Result<Self, CatalogError> {
+        let document = Document::parse(xml)?;
+        let elements = |name: &'static str| {
+            document
+                .descendants()
+                .filter(move |node| node.has_tag_name(name))
+        };
+
+        let base_types: HashMap<String, Node<'_, '_>> = elements("SW-BASE-TYPE")
+            .map(|base_type| (reference(base_type), base_type))
+            .collect();
+
+        // the senders of all messages that are referenced instead of contained by their context
+        let mut referenced: HashMap<&str, Vec<Sender<'_>>> = HashMap::new();
+        for context in elements("DLT-CONTEXT") {
+            for message_ref in context
+                .descendants()
+                .filter(|node| node.has_tag_name("DLT-MESSAGE-REF"))
+            {
+                if let Some(path) = message_ref.text() {
+                    referenced
+                        .entry(path.trim())
+                        .or_default()
+                        .push(sender(context)?);
+                }
+            }
+        }
+
+        let mut catalog = Catalog::new();
+        for message in elements("DLT-MESSAGE") {
+            let senders = match message
+                .ancestors()
+                .find(|node| node.has_tag_name("DLT-CONTEXT"))
+            {
+                Some(context) => vec![sender(context)?],
+                None => referenced
+                    .remove(reference(message).as_str())
+                    .unwrap_or_default(),
+            };
+            // exports often contain whole message collections, of which only some are used
+            if senders.is_empty() {
+                continue;
+            }
+
+            let name = child_text(message, "SHORT-NAME").unwrap_or_default();
+            let message_id = child_text(message, "MESSAGE-ID")
+                .and_then(parse_integer)
+                .ok_or_else(|| invalid("message id of", name))?;
+
+            let parts = match child(message, "DLT-ARGUMENTS") {
+                Some(arguments) => arguments
+                    .children()
+                    .filter(|node| node.has_tag_name("DLT-ARGUMENT"))
+                    .map(|argument| frame_part(argument, &base_types))
+                    .collect::<Result<Vec<_>, _>>()?,
+                None => Vec::new(),
+            };
+
+            for sender in senders {
+                catalog.insert(
+                    sender.ecu_id,
+                    Frame {
+                        message_id,
+                        application_id: sender.application_id.map(str::to_string),
+                        context_id: sender.context_id.map(str::to_string),
+                        type_info: child_text(message, "MESSAGE-TYPE-INFO").and_then(type_info),
+                        parts: parts.clone(),
+                    },
+                );
+            }
+        }
+
+        Ok(catalog)
+    }
+}
+
+fn invalid(what: &str, name: &str) -> CatalogError {
+    CatalogError::Invalid(format!("{what} {name}"))
+}
+
+/// The absolute path of an element, as used in references
+fn reference(node: Node<'_, '_>) -> String {
+    let mut names: Vec<_> = node
+        .ancestors()
+        .filter_map(|ancestor| child_text(ancestor, "SHORT-NAME"))
+        .collect();
+    names.reverse();
+    format!("/{}", names.join("/"))
+}
+
+/// Accepts decimal and hexadecimal (`0x`) integers, like AUTOSAR does
+fn parse_integer(text: &str) -> Option<u32> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// The ECU, application and context a `DLT-CONTEXT` belongs to
+fn sender<'a>(context: Node<'a, '_>) -> Result<Sender<'a>, CatalogError> {
+    let ancestor = |name| context.ancestors().find(|node| node.has_tag_name(name));
+    let ecu_id = ancestor("DLT-ECU")
+        .and_then(|ecu| child_text(ecu, "ECU-ID"))
+        .ok_or_else(|| {
+            invalid(
+                "no ECU for context",
+                child_text(context, "SHORT-NAME").unwrap_or_default(),
+            )
+        })?;
+    Ok(Sender {
+        ecu_id,
+        application_id: ancestor("DLT-APPLICATION")
+            .and_then(|application| child_text(application, "APPLICATION-ID")),
+        context_id: child_text(context, "CONTEXT-ID"),
+    })
+}
+
+fn frame_part(
+    argument: Node<'_, '_>,
+    base_types: &HashMap<String, Node<'_, '_>>,
+) -> Result<FramePart, CatalogError> {
+    let name = child_text(argument, "SHORT-NAME").unwrap_or_default();
+    if let Some(text) = child_text(argument, "PREDEFINED-TEXT") {
+        return Ok(FramePart::Text(text.to_string()));
+    }
+    if child(argument, "DLT-ARGUMENT-ENTRYS").is_some() {
+        return Err(invalid("nested arguments are not supported in", name));
+    }
+
+    let base_type = child(argument, "NETWORK-REPRESENTATION")
+        .and_then(|representation| {
+            representation
+                .descendants()
+                .find(|node| node.has_tag_name("BASE-TYPE-REF"))
+        })
+        .and_then(|base_type| base_type.text())
+        .and_then(|path| base_types.get(path.trim()))
+        .ok_or_else(|| invalid("missing base type of argument", name))?;
+
+    let size = child_text(*base_type, "BASE-TYPE-SIZE").and_then(parse_integer);
+    let encoding = child_text(*base_type, "BASE-TYPE-ENCODING").unwrap_or("NONE");
+    let variable_length = child_text(argument, "VARIABLE-LENGTH") == Some("true");
+
+    let signal_type = match (encoding, size) {
+        ("BOOLEAN", _) => SignalType::Bool,
+        ("UTF-8", _) => SignalType::Utf8,
+        ("ISO-8859-1" | "WINDOWS-1252", _) => SignalType::Ascii,
+        ("NONE", _) if variable_length => SignalType::Raw,
+        ("NONE", Some(8)) => SignalType::U8,
+        ("NONE", Some(16)) => SignalType::U16,
+        ("NONE", Some(32)) => SignalType::U32,
+        ("NONE", Some(64)) => SignalType::U64,
+        ("2C", Some(8)) => SignalType::I8,
+        ("2C", Some(16)) => SignalType::I16,
+        ("2C", Some(32)) => SignalType::I32,
+        ("2C", Some(64)) => SignalType::I64,
+        ("IEEE754", Some(32)) => SignalType::F32,
+        ("IEEE754", Some(64)) => SignalType::F64,
+        _ => return Err(invalid("unsupported base type of argument", name)),
+    };
+    Ok(FramePart::Signal(signal_type))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::header::{LogInfo, MessageTypeInfo};
+    use crate::DltFile;
+
+    const ARXML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<AUTOSAR xmlns="http://autosar.org/schema/r4.0">
+  <AR-PACKAGES>
+    <AR-PACKAGE>
+      <SHORT-NAME>BaseTypes</SHORT-NAME>
+      <ELEMENTS>
+        <SW-BASE-TYPE>
+          <SHORT-NAME>sint16</SHORT-NAME>
+          <BASE-TYPE-SIZE>16</BASE-TYPE-SIZE>
+          <BASE-TYPE-ENCODING>2C</BASE-TYPE-ENCODING>
+        </SW-BASE-TYPE>
+        <SW-BASE-TYPE>
+          <SHORT-NAME>string</SHORT-NAME>
+          <BASE-TYPE-ENCODING>UTF-8</BASE-TYPE-ENCODING>
+        </SW-BASE-TYPE>
+      </ELEMENTS>
+    </AR-PACKAGE>
+    <AR-PACKAGE>
+      <SHORT-NAME>Dlt</SHORT-NAME>
+      <ELEMENTS>
+        <DLT-MESSAGE>
+          <SHORT-NAME>Temperature</SHORT-NAME>
+          <DLT-ARGUMENTS>
+            <DLT-ARGUMENT>
+              <SHORT-NAME>label</SHORT-NAME>
+              <PREDEFINED-TEXT>Temperature:</PREDEFINED-TEXT>
+            </DLT-ARGUMENT>
+            <DLT-ARGUMENT>
+              <SHORT-NAME>value</SHORT-NAME>
+              <NETWORK-REPRESENTATION>
+                <SW-DATA-DEF-PROPS-VARIANTS>
+                  <SW-DATA-DEF-PROPS-CONDITIONAL>
+                    <BASE-TYPE-REF DEST="SW-BASE-TYPE">/BaseTypes/sint16</BASE-TYPE-REF>
+                  </SW-DATA-DEF-PROPS-CONDITIONAL>
+                </SW-DATA-DEF-PROPS-VARIANTS>
+              </NETWORK-REPRESENTATION>
+            </DLT-ARGUMENT>
+            <DLT-ARGUMENT>
+              <SHORT-NAME>sensor</SHORT-NAME>
+              <NETWORK-REPRESENTATION>
+                <BASE-TYPE-REF DEST="SW-BASE-TYPE">/BaseTypes/string</BASE-TYPE-REF>
+              </NETWORK-REPRESENTATION>
+              <VARIABLE-LENGTH>true</VARIABLE-LENGTH>
+            </DLT-ARGUMENT>
+          </DLT-ARGUMENTS>
+          <MESSAGE-ID>0x0a</MESSAGE-ID>
+          <MESSAGE-TYPE-INFO>DLT-LOG-WARN</MESSAGE-TYPE-INFO>
+        </DLT-MESSAGE>
+        <DLT-ECU>
+          <SHORT-NAME>Ecu1</SHORT-NAME>
+          <ECU-ID>ECU1</ECU-ID>
+          <APPLICATIONS>
+            <DLT-APPLICATION>
+              <SHORT-NAME>App1</SHORT-NAME>
+              <APPLICATION-ID>APP1</APPLICATION-ID>
+              <CONTEXTS>
+                <DLT-CONTEXT>
+                  <SHORT-NAME>Ctx1</SHORT-NAME>
+                  <CONTEXT-ID>CTX1</CONTEXT-ID>
+                  <DLT-MESSAGE-REFS>
+                    <DLT-MESSAGE-REF DEST="DLT-MESSAGE">/Dlt/Temperature</DLT-MESSAGE-REF>
+                  </DLT-MESSAGE-REFS>
+                </DLT-CONTEXT>
+              </CONTEXTS>
+            </DLT-APPLICATION>
+          </APPLICATIONS>
+        </DLT-ECU>
+      </ELEMENTS>
+    </AR-PACKAGE>
+  </AR-PACKAGES>
+</AUTOSAR>"#;
+
+    #[test]
+    fn load_messages() {
+        let catalog = Catalog::from_arxml(ARXML).unwrap();
+        assert_eq!(catalog.len(), 1);
+        let frame = catalog.frame("ECU1", None, None, 10).unwrap();
+        assert_eq!(frame.application_id.as_deref(), Some("APP1"));
+        assert_eq!(frame.context_id.as_deref(), Some("CTX1"));
+        assert_eq!(frame.type_info, Some(MessageTypeInfo::Log(LogInfo::Warn)));
+        assert_eq!(
+            frame.parts,
+            [
+                FramePart::Text("Temperature:".to_string()),
+                FramePart::Signal(SignalType::I16),
+                FramePart::Signal(SignalType::Utf8),
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_message() {
+        let catalog = Catalog::from_arxml(ARXML).unwrap();
+        // a non-verbose message without extended header and the message id 10
+        let data = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU1\
+                     \x20\x00\x00\x10\
+                     \x0a\x00\x00\x00\xf6\xff\x04\x00in\x00\x00";
+        let message = DltFile::new(data).next().unwrap().unwrap();
+        let arguments = catalog
+            .decode(&message)
+            .unwrap()
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(arguments, ["Temperature:", "-10", "in"]);
+    }
+
+    #[test]
+    fn skip_unreferenced_messages() {
+        // another message in the collection, which no context references
+        let unused = r#"<DLT-MESSAGE>
+          <SHORT-NAME>Unused</SHORT-NAME>
+          <MESSAGE-ID>11</MESSAGE-ID>
+        </DLT-MESSAGE>
+        <DLT-ECU>"#;
+        let catalog = Catalog::from_arxml(&ARXML.replacen("<DLT-ECU>", unused, 1)).unwrap();
+        assert_eq!(catalog.len(), 1);
+        assert!(catalog.frame("ECU1", None, None, 10).is_some());
+        assert!(catalog.frame("ECU1", None, None, 11).is_none());
+
+        let catalog =
+            Catalog::from_arxml(&ARXML.replace("/Dlt/Temperature", "/Dlt/Other")).unwrap();
+        assert!(catalog.is_empty());
+    }
+
+    #[test]
+    fn invalid_catalogs() {
+        assert!(matches!(
+            Catalog::from_arxml("<AUTOSAR"),
+            Err(CatalogError::Xml(_))
+        ));
+        assert!(matches!(
+            Catalog::from_arxml(
+                &ARXML.replace("<BASE-TYPE-ENCODING>2C", "<BASE-TYPE-ENCODING>BCD-P")
+            ),
+            Err(CatalogError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,397 @@
+//! Loading non-verbose message descriptions from FIBEX files, as used by the dlt-viewer.
+//!
+//! Every `FRAME` describes one message, its id is part of the frame's `ID` attribute (`ID_<message id>`)
+//! and its application id, context id and log level are in the `MANUFACTURER-EXTENSION`.
+//! The frame consists of `PDU`s, which are either static text (`DESC`) or reference a `SIGNAL`,
+//! whose type follows from its id (e.g. `S_UINT16`) or from its `CODING`.
+//! A frame belongs to the `ECU` whose output ports reference one of its `FRAME-TRIGGERING`s,
+//! or to the only `ECU` of the file.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use roxmltree::{Document, Node};
+
+use crate::{
+    catalog::{
+        xml::{child, child_text, type_info},
+        Catalog, Frame, FramePart, SignalType,
+    },
+    error::CatalogError,
+};
+
+impl Catalog {
+    /// Loads all messages described in the FIBEX file at `path`
+    pub fn load_fibex(path: impl AsRef<Path>) -> Result<Self, CatalogError> {
+        Self::from_fibex(&fs::read_to_string(path)?)
+    }
+
+    /// Loads all messages described in a FIBEX document
+    pub fn from_fibex(xml: &str) -> Result<Self, CatalogError> {
+        let document = Document::parse(xml)?;
+        let elements = |name: &'static str| {
+            document
+                .descendants()
+                .filter(move |node| node.has_tag_name(name))
+        };
+
+        let ecus: Vec<_> = elements("ECU")
+            .filter_map(|ecu| Some((ecu.attribute("ID")?, ecu)))
+            .collect();
+        let only_ecu_id = match ecus.as_slice() {
+            [] => return Err(CatalogError::Invalid("missing ECU".to_string())),
+            [(ecu_id, _)] => Some(*ecu_id),
+            _ => None,
+        };
+
+        // the frames of each frame triggering, and the ECUs that send them
+        let triggered_frames: HashMap<&str, &str> = elements("FRAME-TRIGGERING")
+            .filter_map(|triggering| {
+                let frame = child(triggering, "FRAME-REF")?.attribute("ID-REF")?;
+                Some((triggering.attribute("ID")?, frame))
+            })
+            .collect();
+        let mut frame_ecus = HashMap::new();
+        for (ecu_id, ecu) in &ecus {
+            for reference in ecu
+                .descendants()
+                .filter(|node| node.has_tag_name("FRAME-TRIGGERING-REF"))
+            {
+                if let Some(frame) = reference
+                    .attribute("ID-REF")
+                    .and_then(|triggering| triggered_frames.get(triggering))
+                {
+                    frame_ecus.insert(*frame, *ecu_id);
+                }
+            }
+        }
+
+        let codings: HashMap<&str, &str> = elements("CODING")
+            .filter_map(|coding| {
+                let base_data_type = child(coding, "CODED-TYPE")?
+                    .attributes()
+                    .find(|attribute| attribute.name() == "BASE-DATA-TYPE")?
+                    .value();
+                Some((coding.attribute("ID")?, base_data_type))
+            })
+            .collect();
+
+        let signals: HashMap<&str, Option<&str>> = elements("SIGNAL")
+            .filter_map(|signal| {
+                let coding =
+                    child(signal, "CODING-REF").and_then(|coding| coding.attribute("ID-REF"));
+                Some((signal.attribute("ID")?, coding))
+            })
+            .collect();
+
+        let mut pdus = HashMap::new();
+        for pdu in elements("PDU") {
+            let Some(id) = pdu.attribute("ID") else {
+                continue;
+            };
+            let parts = match child(pdu, "SIGNAL-INSTANCES") {
+                Some(instances) => sorted_by_sequence_number(instances, "SIGNAL-INSTANCE")
+                    .into_iter()
+                    .map(|instance| {
+                        let signal_id = child(instance, "SIGNAL-REF")
+                            .and_then(|signal| signal.attribute("ID-REF"))
+                            .ok_or_else(|| invalid("signal instance without signal", id))?;
+                        signal_type(signal_id, &signals, &codings).map(FramePart::Signal)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                None => child_text(pdu, "DESC")
+                    .map(|text| FramePart::Text(text.to_string()))
+                    .into_iter()
+                    .collect(),
+            };
+            pdus.insert(id, parts);
+        }
+
+        let mut catalog = Catalog::new();
+        for frame in elements("FRAME") {
+            let Some(id) = frame.attribute("ID") else {
+                continue;
+            };
+            let message_id = id
+                .strip_prefix("ID_")
+                .and_then(|message_id| message_id.parse().ok())
+                .ok_or_else(|| invalid("frame id", id))?;
+            let ecu_id = frame_ecus
+                .get(id)
+                .copied()
+                .or(only_ecu_id)
+                .ok_or_else(|| invalid("frame without ECU", id))?;
+
+            let extension = child(frame, "MANUFACTURER-EXTENSION");
+            let extension_text = |name| extension.and_then(|extension| child_text(extension, name));
+
+            let mut parts = Vec::new();
+            if let Some(instances) = child(frame, "PDU-INSTANCES") {
+                for instance in sorted_by_sequence_number(instances, "PDU-INSTANCE") {
+                    let pdu_id = child(instance, "PDU-REF")
+                        .and_then(|pdu| pdu.attribute("ID-REF"))
+                        .ok_or_else(|| invalid("PDU instance without PDU", id))?;
+                    let pdu = pdus
+                        .get(pdu_id)
+                        .ok_or_else(|| invalid("unknown PDU", pdu_id))?;
+                    parts.extend(pdu.iter().cloned());
+                }
+            }
+
+            catalog.insert(
+                ecu_id,
+                Frame {
+                    message_id,
+                    application_id: extension_text("APPLICATION_ID").map(str::to_string),
+                    context_id: extension_text("CONTEXT_ID").map(str::to_string),
+                    type_info: extension_text("MESSAGE_INFO").and_then(type_info),
+                    parts,
+                },
+            );
+        }
+
+        Ok(catalog)
+    }
+}
+
+fn invalid(what: &str, id: &str) -> CatalogError {
+    CatalogError::Invalid(format!("{what} {id}"))
+}
+
+/// The children called `name`, in the order of their `SEQUENCE-NUMBER`
+fn sorted_by_sequence_number<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &str,
+) -> Vec<Node<'a, 'input>> {
+    let mut children: Vec<_> = node
+        .children()
+        .filter(|child| child.has_tag_name(name))
+        .collect();
+    children.sort_by_key(|child| {
+        child_text(*child, "SEQUENCE-NUMBER").and_then(|number| number.parse::<u32>().ok())
+    });
+    children
+}
+
+/// The type of the signal `signal_id`, either from the naming convention of the dlt-viewer
+/// or from the base data type of its coding
+fn signal_type(
+    signal_id: &str,
+    signals: &HashMap<&str, Option<&str>>,
+    codings: &HashMap<&str, &str>,
+) -> Result<SignalType, CatalogError> {
+    let by_name = match signal_id {
+        "S_BOOL" => Some(SignalType::Bool),
+        "S_UINT8" => Some(SignalType::U8),
+        "S_UINT16" => Some(SignalType::U16),
+        "S_UINT32" => Some(SignalType::U32),
+        "S_UINT64" => Some(SignalType::U64),
+        "S_SINT8" => Some(SignalType::I8),
+        "S_SINT16" => Some(SignalType::I16),
+        "S_SINT32" => Some(SignalType::I32),
+        "S_SINT64" => Some(SignalType::I64),
+        "S_FLOA32" => Some(SignalType::F32),
+        "S_FLOA64" => Some(SignalType::F64),
+        "S_STRG_ASCII" => Some(SignalType::Ascii),
+        "S_STRG_UTF8" => Some(SignalType::Utf8),
+        "S_RAWD" | "S_RAW" => Some(SignalType::Raw),
+        _ => None,
+    };
+    if let Some(signal_type) = by_name {
+        return Ok(signal_type);
+    }
+
+    let base_data_type = signals
+        .get(signal_id)
+        .copied()
+        .flatten()
+        .and_then(|coding| codings.get(coding));
+    match base_data_type {
+        Some(&"A_UINT8") => Ok(SignalType::U8),
+        Some(&"A_UINT16") => Ok(SignalType::U16),
+        Some(&"A_UINT32") => Ok(SignalType::U32),
+        Some(&"A_UINT64") => Ok(SignalType::U64),
+        Some(&"A_INT8") => Ok(SignalType::I8),
+        Some(&"A_INT16") => Ok(SignalType::I16),
+        Some(&"A_INT32") => Ok(SignalType::I32),
+        Some(&"A_INT64") => Ok(SignalType::I64),
+        Some(&"A_FLOAT32") => Ok(SignalType::F32),
+        Some(&"A_FLOAT64") => Ok(SignalType::F64),
+        Some(&"A_ASCIISTRING") => Ok(SignalType::Ascii),
+        Some(&"A_UNICODE2STRING") => Ok(SignalType::Utf8),
+        Some(&"A_BYTEFIELD") => Ok(SignalType::Raw),
+        _ => Err(invalid("unsupported signal", signal_id)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::header::{LogInfo, MessageTypeInfo};
+    use crate::DltFile;
+
+    const FIBEX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<fx:FIBEX xmlns:fx="http://www.asam.net/xml/fbx" xmlns:ho="http://www.asam.net/xml" VERSION="3.1.0">
+  <fx:ELEMENTS>
+    <fx:ECUS>
+      <fx:ECU ID="ECU1"><ho:SHORT-NAME>ECU1</ho:SHORT-NAME></fx:ECU>
+    </fx:ECUS>
+    <fx:PDUS>
+      <fx:PDU ID="PDU_10_0">
+        <ho:SHORT-NAME>PDU_10_0</ho:SHORT-NAME>
+        <ho:DESC>Temperature:</ho:DESC>
+        <fx:BYTE-LENGTH>0</fx:BYTE-LENGTH>
+      </fx:PDU>
+      <fx:PDU ID="PDU_10_1">
+        <ho:SHORT-NAME>PDU_10_1</ho:SHORT-NAME>
+        <fx:BYTE-LENGTH>2</fx:BYTE-LENGTH>
+        <fx:SIGNAL-INSTANCES>
+          <fx:SIGNAL-INSTANCE ID="S_10_1">
+            <fx:SEQUENCE-NUMBER>0</fx:SEQUENCE-NUMBER>
+            <fx:SIGNAL-REF ID-REF="S_SINT16"/>
+          </fx:SIGNAL-INSTANCE>
+        </fx:SIGNAL-INSTANCES>
+      </fx:PDU>
+      <fx:PDU ID="PDU_10_2">
+        <ho:SHORT-NAME>PDU_10_2</ho:SHORT-NAME>
+        <fx:SIGNAL-INSTANCES>
+          <fx:SIGNAL-INSTANCE ID="S_10_2">
+            <fx:SEQUENCE-NUMBER>0</fx:SEQUENCE-NUMBER>
+            <fx:SIGNAL-REF ID-REF="S_SENSOR"/>
+          </fx:SIGNAL-INSTANCE>
+        </fx:SIGNAL-INSTANCES>
+      </fx:PDU>
+    </fx:PDUS>
+    <fx:FRAMES>
+      <fx:FRAME ID="ID_10">
+        <ho:SHORT-NAME>ID_10</ho:SHORT-NAME>
+        <fx:MANUFACTURER-EXTENSION>
+          <MESSAGE_TYPE>DLT_TYPE_LOG</MESSAGE_TYPE>
+          <MESSAGE_INFO>DLT_LOG_WARN</MESSAGE_INFO>
+          <APPLICATION_ID>APP1</APPLICATION_ID>
+          <CONTEXT_ID>CTX1</CONTEXT_ID>
+        </fx:MANUFACTURER-EXTENSION>
+        <fx:PDU-INSTANCES>
+          <fx:PDU-INSTANCE ID="P_10_2">
+            <fx:PDU-REF ID-REF="PDU_10_2"/>
+            <fx:SEQUENCE-NUMBER>2</fx:SEQUENCE-NUMBER>
+          </fx:PDU-INSTANCE>
+          <fx:PDU-INSTANCE ID="P_10_0">
+            <fx:PDU-REF ID-REF="PDU_10_0"/>
+            <fx:SEQUENCE-NUMBER>0</fx:SEQUENCE-NUMBER>
+          </fx:PDU-INSTANCE>
+          <fx:PDU-INSTANCE ID="P_10_1">
+            <fx:PDU-REF ID-REF="PDU_10_1"/>
+            <fx:SEQUENCE-NUMBER>1</fx:SEQUENCE-NUMBER>
+          </fx:PDU-INSTANCE>
+        </fx:PDU-INSTANCES>
+      </fx:FRAME>
+    </fx:FRAMES>
+    <fx:SIGNALS>
+      <fx:SIGNAL ID="S_SENSOR">
+        <ho:SHORT-NAME>S_SENSOR</ho:SHORT-NAME>
+        <fx:CODING-REF ID-REF="C_ASCII"/>
+      </fx:SIGNAL>
+    </fx:SIGNALS>
+  </fx:ELEMENTS>
+  <fx:PROCESSING-INFORMATION>
+    <fx:CODINGS>
+      <fx:CODING ID="C_ASCII">
+        <ho:SHORT-NAME>C_ASCII</ho:SHORT-NAME>
+        <ho:CODED-TYPE ho:BASE-DATA-TYPE="A_ASCIISTRING" CATEGORY="LEADING-LENGTH-INFO-TYPE"/>
+      </fx:CODING>
+    </fx:CODINGS>
+  </fx:PROCESSING-INFORMATION>
+</fx:FIBEX>"#;
+
+    #[test]
+    fn load_frames() {
+        let catalog = Catalog::from_fibex(FIBEX).unwrap();
+        let frame = catalog.frame("ECU1", None, None, 10).unwrap();
+        assert_eq!(frame.application_id.as_deref(), Some("APP1"));
+        assert_eq!(frame.context_id.as_deref(), Some("CTX1"));
+        assert_eq!(frame.type_info, Some(MessageTypeInfo::Log(LogInfo::Warn)));
+        assert_eq!(
+            frame.parts,
+            [
+                FramePart::Text("Temperature:".to_string()),
+                FramePart::Signal(SignalType::I16),
+                FramePart::Signal(SignalType::Ascii),
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_message() {
+        let catalog = Catalog::from_fibex(FIBEX).unwrap();
+        // a non-verbose message with extended header and the message id 10
+        let data = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU1\
+                     \x21\x00\x00\x1a\
+                     \x30\x00APP1CTX1\
+                     \x0a\x00\x00\x00\x15\x00\x04\x00in\x00\x00";
+        let message = DltFile::new(data).next().unwrap().unwrap();
+        let arguments = catalog
+            .decode(&message)
+            .unwrap()
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(arguments, ["Temperature:", "21", "in"]);
+    }
+
+    #[test]
+    fn frames_of_multiple_ecus() {
+        let ecus = r#"<fx:ECU ID="ECU1"><ho:SHORT-NAME>ECU1</ho:SHORT-NAME></fx:ECU>
+      <fx:ECU ID="ECU2">
+        <ho:SHORT-NAME>ECU2</ho:SHORT-NAME>
+        <fx:CONNECTORS>
+          <fx:CONNECTOR ID="CON_ECU2">
+            <fx:OUTPUTS>
+              <fx:OUTPUT-PORT ID="OP_10"><fx:FRAME-TRIGGERING-REF ID-REF="FT_10"/></fx:OUTPUT-PORT>
+            </fx:OUTPUTS>
+          </fx:CONNECTOR>
+        </fx:CONNECTORS>
+      </fx:ECU>"#;
+        let fibex = FIBEX.replace(
+            r#"<fx:ECU ID="ECU1"><ho:SHORT-NAME>ECU1</ho:SHORT-NAME></fx:ECU>"#,
+            ecus,
+        );
+        // without a frame triggering it is unknown which ECU sends the frame
+        assert!(matches!(
+            Catalog::from_fibex(&fibex),
+            Err(CatalogError::Invalid(_))
+        ));
+
+        let fibex = fibex.replace(
+            "<fx:FRAMES>",
+            r#"<fx:CHANNELS>
+      <fx:CHANNEL ID="CH">
+        <fx:FRAME-TRIGGERINGS>
+          <fx:FRAME-TRIGGERING ID="FT_10"><fx:FRAME-REF ID-REF="ID_10"/></fx:FRAME-TRIGGERING>
+        </fx:FRAME-TRIGGERINGS>
+      </fx:CHANNEL>
+    </fx:CHANNELS>
+    <fx:FRAMES>"#,
+        );
+        let catalog = Catalog::from_fibex(&fibex).unwrap();
+        assert!(catalog.frame("ECU1", None, None, 10).is_none());
+        assert!(catalog.frame("ECU2", None, None, 10).is_some());
+    }
+
+    #[test]
+    fn invalid_catalogs() {
+        assert!(matches!(
+            Catalog::from_fibex("<FIBEX"),
+            Err(CatalogError::Xml(_))
+        ));
+        assert!(matches!(
+            Catalog::from_fibex("<FIBEX/>"),
+            Err(CatalogError::Invalid(_))
+        ));
+        assert!(matches!(
+            Catalog::from_fibex(&FIBEX.replace("S_SINT16", "S_UNKNOWN")),
+            Err(CatalogError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,40 @@
+//! Helpers shared by the XML based catalog formats.
+
+use roxmltree::Node;
+
+use crate::header::{BusInfo, LogInfo, MessageTypeInfo, TraceInfo};
+
+/// The first child element called `name`, regardless of its namespace
+pub(crate) fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+/// The trimmed text of the first child element called `name`
+pub(crate) fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name)?.text().map(str::trim)
+}
+
+/// The message type info from its name in the DLT library, e.g. `DLT_LOG_INFO`.
+/// Dashes are accepted instead of underscores, as they are common in AUTOSAR models.
+pub(crate) fn type_info(message_info: &str) -> Option<MessageTypeInfo> {
+    Some(match message_info.replace('-', "_").as_str() {
+        "DLT_LOG_FATAL" => MessageTypeInfo::Log(LogInfo::Fatal),
+        "DLT_LOG_ERROR" => MessageTypeInfo::Log(LogInfo::Error),
+        "DLT_LOG_WARN" => MessageTypeInfo::Log(LogInfo::Warn),
+        "DLT_LOG_INFO" => MessageTypeInfo::Log(LogInfo::Info),
+        "DLT_LOG_DEBUG" => MessageTypeInfo::Log(LogInfo::Debug),
+        "DLT_LOG_VERBOSE" => MessageTypeInfo::Log(LogInfo::Verbose),
+        "DLT_TRACE_VARIABLE" => MessageTypeInfo::Trace(TraceInfo::Variable),
+        "DLT_TRACE_FUNCTION_IN" => MessageTypeInfo::Trace(TraceInfo::FunctionIn),
+        "DLT_TRACE_FUNCTION_OUT" => MessageTypeInfo::Trace(TraceInfo::FunctionOut),
+        "DLT_TRACE_STATE" => MessageTypeInfo::Trace(TraceInfo::State),
+        "DLT_TRACE_VFB" => MessageTypeInfo::Trace(TraceInfo::Vfb),
+        "DLT_NW_TRACE_IPC" => MessageTypeInfo::Bus(BusInfo::Ipc),
+        "DLT_NW_TRACE_CAN" => MessageTypeInfo::Bus(BusInfo::Can),
+        "DLT_NW_TRACE_FLEXRAY" => MessageTypeInfo::Bus(BusInfo::Flexray),
+        "DLT_NW_TRACE_MOST" => MessageTypeInfo::Bus(BusInfo::Most),
+        "DLT_NW_TRACE_ETHERNET" => MessageTypeInfo::Bus(BusInfo::Ethernet),
+        "DLT_NW_TRACE_SOMEIP" => MessageTypeInfo::Bus(BusInfo::SomeIP),
+        _ => return None,
+    })
+}
@@ -0,0 +1,235 @@
+//! Receiving messages directly from a dlt-daemon over TCP.
+//!
+//! The daemon sends messages without storage headers, so each message gets a synthesized one
+//! with the time it was received and the ECU id of its standard header, see [`Framing::Raw`].
+
+use std::{
+    io::{self, ErrorKind, Read},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    decoder::DecoderRegistry,
+    error::ReadError,
+    message::DltMessage,
+    parser::{DltParser, Framing, NoMessage},
+    payload::StringDecoding,
+    reader::OwnedMessage,
+};
+
+/// The port dlt-daemon listens on by default
+pub const DEFAULT_PORT: u16 = 3490;
+
+const RECEIVE_BUFFER_SIZE: usize = 1 << 16;
+
+/// A connection to a dlt-daemon, which is reestablished whenever it is lost
+#[derive(Debug)]
+pub struct DltClient {
+    addresses: Vec<SocketAddr>,
+    stream: Option<TcpStream>,
+    parser: DltParser,
+    buf: Box<[u8]>,
+    reconnect: bool,
+    min_backoff: Duration,
+    max_backoff: Duration,
+    // how long to wait before the next connection attempt
+    backoff: Duration,
+}
+
+impl DltClient {
+    /// Connects to the daemon at `address`, e.g. `("192.168.0.2", DEFAULT_PORT)`
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let addresses = address.to_socket_addrs()?.collect::<Vec<_>>();
+        let stream = TcpStream::connect(&addresses[..])?;
+        Ok(Self {
+            addresses,
+            stream: Some(stream),
+            parser: DltParser::new(Framing::Raw),
+            buf: vec![0; RECEIVE_BUFFER_SIZE].into_boxed_slice(),
+            reconnect: true,
+            min_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            backoff: Duration::from_millis(100),
+        })
+    }
+
+    /// Sets whether to reconnect when the connection is lost, which is the default, or to end
+    pub fn with_reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// Sets the time to wait before reconnecting, which doubles after each failed attempt up to `max`
+    pub fn with_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self.backoff = min;
+        self
+    }
+
+    /// Sets the ECU id of messages without one in their standard header
+    pub fn with_ecu_id(mut self, ecu_id: &str) -> Self {
+        self.parser = self.parser.with_ecu_id(ecu_id);
+        self
+    }
+
+    /// Sets how string arguments that are not valid UTF-8 are handled in all messages
+    pub fn with_string_decoding(mut self, string_decoding: StringDecoding) -> Self {
+        self.parser = self.parser.with_string_decoding(string_decoding);
+        self
+    }
+
+    /// Sets the decoders that format the payloads of all messages, see [`DltMessage::set_decoder`]
+    pub fn with_decoders(mut self, decoders: Arc<DecoderRegistry>) -> Self {
+        self.parser = self.parser.with_decoders(decoders);
+        self
+    }
+
+    /// Whether the client is currently connected
+    pub fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Receives the next message, which borrows the client until the next call.
+    ///
+    /// Lost connections and failed attempts to reconnect are yielded as I/O errors,
+    /// the next call waits for the backoff and tries again.
+    /// Returns `None` once the connection is lost if reconnecting is disabled.
+    pub fn next_message(&mut self) -> Option<Result<DltMessage<'_>, ReadError>> {
+        let length = loop {
+            match self.parser.next_length() {
+                Ok(length) => break length,
+                Err(NoMessage::Error(err)) => return Some(Err(err.into())),
+                Err(NoMessage::Incomplete { .. }) => {
+                    if let Err(err) = self.receive() {
+                        return Some(Err(err.into()));
+                    }
+                }
+            }
+            if self.stream.is_none() && !self.reconnect {
+                return None;
+            }
+        };
+        Some(self.parser.take(length).map_err(ReadError::from))
+    }
+
+    /// Feeds the next received data to the parser, reconnecting first if necessary
+    fn receive(&mut self) -> io::Result<()> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None if !self.reconnect => return Ok(()),
+            None => {
+                thread::sleep(self.backoff);
+                self.backoff = (self.backoff * 2).min(self.max_backoff);
+                self.stream.insert(TcpStream::connect(&self.addresses[..])?)
+            }
+        };
+        let read = match stream.read(&mut self.buf) {
+            Ok(0) => Err(ErrorKind::UnexpectedEof.into()),
+            Err(err) if err.kind() == ErrorKind::Interrupted => return Ok(()),
+            result => result,
+        };
+        match read {
+            Ok(read) => {
+                self.backoff = self.min_backoff;
+                self.parser.feed(&self.buf[..read]);
+                Ok(())
+            }
+            Err(err) => {
+                // a partial message will never be completed by the next connection
+                self.stream = None;
+                self.parser.reset();
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Yields owned copies of the messages, use [`DltClient::next_message`] to avoid the copies
+impl Iterator for DltClient {
+    type Item = Result<OwnedMessage, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let decoding = self.parser.decoding().clone();
+        Some(
+            self.next_message()?
+                .map(|message| OwnedMessage::new(&message, decoding)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::{io::Write, net::TcpListener};
+
+    use super::*;
+
+    // a verbose log message without storage header, with the ECU id in the standard header
+    const MESSAGE: &[u8] = b"\x25\x00\x00\x1bECU1\
+                             \x41\x01APP1CTX1\
+                             \x00\x82\x00\x00\x03\x00hi\x00";
+
+    #[test]
+    fn receive_and_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let daemon = thread::spawn(move || {
+            // the first connection is lost in the middle of the second message
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(MESSAGE).unwrap();
+            stream.write_all(&MESSAGE[..10]).unwrap();
+            drop(stream);
+            let (mut stream, _) = listener.accept().unwrap();
+            for chunk in MESSAGE.chunks(3) {
+                stream.write_all(chunk).unwrap();
+            }
+        });
+
+        let mut client = DltClient::connect(address)
+            .unwrap()
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(10));
+        let message = client.next().unwrap().unwrap();
+        assert_eq!(message.as_bytes(), MESSAGE);
+        assert_eq!(message.message().ecu_id(), "ECU1");
+        assert!(message.message().storage_header.is_synthesized());
+
+        assert!(matches!(client.next(), Some(Err(ReadError::Io(_)))));
+        assert!(!client.is_connected());
+        let message = client.next_message().unwrap().unwrap();
+        assert_eq!(message.as_bytes(), MESSAGE);
+        assert_eq!(message.payload.to_string(), "hi ");
+
+        daemon.join().unwrap();
+        let mut client = client.with_reconnect(false);
+        assert!(matches!(client.next(), Some(Err(ReadError::Io(_)))));
+        assert!(client.next().is_none());
+    }
+
+    #[test]
+    fn resync_after_garbage() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let daemon = thread::spawn(move || {
+            // a standard header length smaller than the header itself
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"\x20\x00\x00\x02").unwrap();
+            for chunk in [MESSAGE, MESSAGE, MESSAGE].concat().chunks(3) {
+                thread::sleep(Duration::from_millis(1));
+                stream.write_all(chunk).unwrap();
+            }
+        });
+
+        let mut client = DltClient::connect(address).unwrap().with_reconnect(false);
+        assert!(matches!(client.next(), Some(Err(ReadError::Dlt(_)))));
+        for _ in 0..3 {
+            let message = client.next().unwrap().unwrap();
+            assert_eq!(message.as_bytes(), MESSAGE);
+        }
+        daemon.join().unwrap();
+        assert!(matches!(client.next(), Some(Err(ReadError::Io(_)))));
+    }
+}
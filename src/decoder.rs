@@ -0,0 +1,254 @@
+//! Custom decoding of payloads.
+//!
+//! A [`PayloadDecoder`] formats the payload of the messages it is registered for in a
+//! [`DecoderRegistry`], instead of the default formatting of [`Payload`](crate::payload::Payload).
+//! Messages from a [`DltFile`](crate::DltFile), or any other reader, with a registry use their decoder
+//! in their `Display` implementation, so the decoded payload shows up wherever messages are formatted.
+//! Readers that own their data share the registry with the messages they yield through an `Arc`.
+
+use std::fmt::{self, Formatter};
+
+use crate::{catalog::Catalog, header::MessageType, message::DltMessage};
+
+/// Formats the payload of a message
+pub trait PayloadDecoder: Send + Sync {
+    /// Writes the payload of `message`.
+    /// Formatting `message` itself here would recurse, as its `Display` implementation calls this decoder.
+    fn fmt(&self, message: &DltMessage<'_>, f: &mut Formatter<'_>) -> fmt::Result;
+}
+
+impl<F> PayloadDecoder for F
+where
+    F: Fn(&DltMessage<'_>, &mut Formatter<'_>) -> fmt::Result + Send + Sync,
+{
+    fn fmt(&self, message: &DltMessage<'_>, f: &mut Formatter<'_>) -> fmt::Result {
+        self(message, f)
+    }
+}
+
+impl fmt::Debug for dyn PayloadDecoder + '_ {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "PayloadDecoder")
+    }
+}
+
+/// Formats non-verbose payloads with the arguments described in the catalog,
+/// just like verbose payloads. Payloads without a description are formatted as usual.
+impl PayloadDecoder for Catalog {
+    fn fmt(&self, message: &DltMessage<'_>, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.decode(message) {
+            Some(Ok(arguments)) => arguments
+                .iter()
+                .try_for_each(|argument| write!(f, "{argument} ")),
+            Some(Err(_)) => write!(f, "ARGERROR"),
+            None => write!(f, "{}", message.payload),
+        }
+    }
+}
+
+/// Which messages a decoder is registered for, every field that is `None` matches any message
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DecoderKey {
+    /// The ECU that sent the message, see [`DltMessage::sender_ecu_id`]
+    pub ecu_id: Option<String>,
+    pub application_id: Option<String>,
+    pub context_id: Option<String>,
+    /// Only non-verbose messages have a message id
+    pub message_id: Option<u32>,
+    pub message_type: Option<MessageType>,
+}
+
+impl DecoderKey {
+    /// A key that matches every message
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ecu_id(mut self, ecu_id: &str) -> Self {
+        self.ecu_id = Some(ecu_id.to_string());
+        self
+    }
+
+    pub fn with_application_id(mut self, application_id: &str) -> Self {
+        self.application_id = Some(application_id.to_string());
+        self
+    }
+
+    pub fn with_context_id(mut self, context_id: &str) -> Self {
+        self.context_id = Some(context_id.to_string());
+        self
+    }
+
+    pub fn with_message_id(mut self, message_id: u32) -> Self {
+        self.message_id = Some(message_id);
+        self
+    }
+
+    pub fn with_message_type(mut self, message_type: MessageType) -> Self {
+        self.message_type = Some(message_type);
+        self
+    }
+
+    pub fn matches(&self, message: &DltMessage<'_>) -> bool {
+        fn matches<T: PartialEq>(expected: &Option<T>, actual: Option<T>) -> bool {
+            expected.is_none() || *expected == actual
+        }
+
+        matches(&self.ecu_id.as_deref(), Some(message.sender_ecu_id()))
+            && matches(&self.application_id.as_deref(), message.app_id())
+            && matches(&self.context_id.as_deref(), message.context_id())
+            && matches(
+                &self.message_id,
+                message
+                    .payload
+                    .as_non_verbose()
+                    .map(|payload| payload.message_id()),
+            )
+            && matches(&self.message_type, message.message_type())
+    }
+
+    /// How many fields are set, more specific keys take precedence over less specific ones
+    fn specificity(&self) -> usize {
+        [
+            self.ecu_id.is_some(),
+            self.application_id.is_some(),
+            self.context_id.is_some(),
+            self.message_id.is_some(),
+            self.message_type.is_some(),
+        ]
+        .into_iter()
+        .filter(|&set| set)
+        .count()
+    }
+}
+
+/// The decoders for the payloads of different messages
+#[derive(Debug, Default)]
+pub struct DecoderRegistry {
+    decoders: Vec<(DecoderKey, Box<dyn PayloadDecoder>)>,
+}
+
+impl DecoderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `decoder` for all messages matching `key`
+    pub fn register(&mut self, key: DecoderKey, decoder: impl PayloadDecoder + 'static) {
+        self.decoders.push((key, Box::new(decoder)));
+    }
+
+    /// The decoder for `message`.
+    /// If multiple keys match, the one with the most fields set wins, then the one registered first.
+    pub fn decoder(&self, message: &DltMessage<'_>) -> Option<&dyn PayloadDecoder> {
+        self.decoders
+            .iter()
+            .filter(|(key, _)| key.matches(message))
+            .fold(
+                None,
+                |best: Option<&(DecoderKey, _)>, candidate| match best {
+                    Some(best) if best.0.specificity() >= candidate.0.specificity() => Some(best),
+                    _ => Some(candidate),
+                },
+            )
+            .map(|(_, decoder)| decoder.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.decoders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decoders.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{DltFile, DltReader};
+
+    // a verbose log message from APP1 CTX1 with a string argument
+    const MESSAGE: &[u8] = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                             \x21\x00\x00\x17\
+                             \x41\x01APP1CTX1\
+                             \x00\x82\x00\x00\x03\x00hi\x00";
+
+    #[test]
+    fn most_specific_decoder_wins() {
+        let mut registry = DecoderRegistry::new();
+        registry.register(
+            DecoderKey::new(),
+            |_: &DltMessage<'_>, f: &mut Formatter<'_>| write!(f, "any"),
+        );
+        registry.register(
+            DecoderKey::new()
+                .with_application_id("APP1")
+                .with_context_id("CTX1"),
+            |message: &DltMessage<'_>, f: &mut Formatter<'_>| {
+                write!(f, "<{}>", message.payload.to_string().trim_end())
+            },
+        );
+        registry.register(
+            DecoderKey::new().with_application_id("APP2"),
+            |_: &DltMessage<'_>, f: &mut Formatter<'_>| write!(f, "other"),
+        );
+        assert_eq!(registry.len(), 3);
+
+        let message = DltFile::new(MESSAGE)
+            .with_decoders(&registry)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert!(message.to_string().ends_with(" verbose 1 <hi>"));
+
+        // without a registry the payload is formatted as usual
+        let message = DltFile::new(MESSAGE).next().unwrap().unwrap();
+        assert!(message.to_string().ends_with(" verbose 1 hi "));
+    }
+
+    #[test]
+    fn decoders_of_readers() {
+        let mut registry = DecoderRegistry::new();
+        registry.register(
+            DecoderKey::new().with_context_id("CTX1"),
+            |_: &DltMessage<'_>, f: &mut Formatter<'_>| write!(f, "decoded"),
+        );
+        let data = [MESSAGE, MESSAGE].concat();
+        let mut reader = DltReader::new(&data[..]).with_decoders(Arc::new(registry));
+
+        let message = reader.next_message().unwrap().unwrap();
+        assert!(message.to_string().ends_with(" verbose 1 decoded"));
+        // owned messages keep the decoder
+        let message = reader.next().unwrap().unwrap();
+        assert!(message
+            .message()
+            .to_string()
+            .ends_with(" verbose 1 decoded"));
+    }
+
+    #[test]
+    fn key_matching() {
+        let message = DltFile::new(MESSAGE).next().unwrap().unwrap();
+        assert!(DecoderKey::new().matches(&message));
+        assert!(DecoderKey::new()
+            .with_ecu_id("ECU")
+            .with_message_type(MessageType::Log)
+            .matches(&message));
+        assert!(!DecoderKey::new().with_ecu_id("ECU1").matches(&message));
+        // verbose messages have no message id
+        assert!(!DecoderKey::new().with_message_id(0).matches(&message));
+
+        // the ECU id of the standard header takes precedence over the one of the logger
+        let data = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00LOG\x00\
+                     \x25\x00\x00\x1bECU1\
+                     \x41\x01APP1CTX1\
+                     \x00\x82\x00\x00\x03\x00hi\x00";
+        let message = DltFile::new(data).next().unwrap().unwrap();
+        assert!(DecoderKey::new().with_ecu_id("ECU1").matches(&message));
+        assert!(!DecoderKey::new().with_ecu_id("LOG").matches(&message));
+    }
+}
@@ -19,10 +19,37 @@ pub enum ParseError {
     #[error("No such argument type: {0}")]
     UnknownArgumentType(u32),
 
+    #[error("Invalid message length: {0}")]
+    InvalidLength(u16),
+
     #[error("Invalid UTF-8 string!")]
     BadUTF8(#[from] simdutf8::basic::Utf8Error),
 }
 
+/// An error while loading the descriptions of non-verbose messages
+#[derive(Debug, Error)]
+pub enum CatalogError {
+    #[error("Could not read catalog: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(any(feature = "fibex", feature = "arxml"))]
+    #[error("Invalid XML: {0}")]
+    Xml(#[from] roxmltree::Error),
+
+    #[error("Invalid catalog: {0}")]
+    Invalid(String),
+}
+
+/// An error while reading messages from a [`DltReader`](crate::reader::DltReader)
+#[derive(Debug, Error)]
+pub enum ReadError {
+    #[error("Could not read messages: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Dlt(#[from] DltError),
+}
+
 #[derive(Debug)]
 pub struct DltError {
     pub(crate) advance_by: Option<usize>,
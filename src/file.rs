@@ -1,44 +1,87 @@
+use crate::decoder::DecoderRegistry;
 use crate::error::DltError;
+use crate::header::StorageHeader;
+#[cfg(feature = "r22-11")]
+use crate::message::v2::VersionedMessage;
 use crate::message::DltMessage;
+use crate::payload::StringDecoding;
 use bytes::Buf;
 
-const MIN_MESSAGE_LENGTH: usize = 16 /*Storage Header*/ + 4 /*Smallest Standard Header, no Extended Header */;
+#[cfg(feature = "mmap")]
+mod mapped;
+#[cfg(feature = "mmap")]
+pub use mapped::MappedDltFile;
+
+const MIN_MESSAGE_LENGTH: usize = StorageHeader::MIN_LENGTH + 4 /*Smallest Standard Header, no Extended Header */;
 #[derive(Debug)]
 pub struct DltFile<'a> {
     buf: &'a [u8],
+    string_decoding: StringDecoding,
+    decoders: Option<&'a DecoderRegistry>,
 }
 
 impl<'a> DltFile<'a> {
     pub fn new(buf: &'a [u8]) -> Self {
-        Self { buf }
+        Self {
+            buf,
+            string_decoding: StringDecoding::default(),
+            decoders: None,
+        }
     }
-}
 
-impl<'a> Iterator for DltFile<'a> {
-    type Item = Result<DltMessage<'a>, DltError>;
+    /// Sets how string arguments that are not valid UTF-8 are handled in all messages of this file
+    pub fn with_string_decoding(mut self, string_decoding: StringDecoding) -> Self {
+        self.string_decoding = string_decoding;
+        self
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Sets the decoders that format the payloads of the messages of this file, see [`DltMessage::set_decoder`]
+    pub fn with_decoders(mut self, decoders: &'a DecoderRegistry) -> Self {
+        self.decoders = Some(decoders);
+        self
+    }
+
+    /// Iterates over messages of both protocol versions, instead of only version 1.
+    /// Otherwise version 2 messages are skipped with a [`ParseError::Unsupported`] error each.
+    ///
+    /// [`ParseError::Unsupported`]: crate::error::ParseError::Unsupported
+    #[cfg(feature = "r22-11")]
+    pub fn versioned(self) -> VersionedDltFile<'a> {
+        VersionedDltFile { file: self }
+    }
+
+    /// Parses the next message with `parse`, which returns the message and its length
+    fn next_message<M>(
+        &mut self,
+        parse: impl FnOnce(&'a [u8]) -> Result<(M, usize), DltError>,
+    ) -> Option<Result<M, DltError>> {
         if !self.buf.has_remaining() {
             None
         } else {
-            match DltMessage::from_slice(self.buf) {
-                Ok(message) => {
-                    self.buf.advance(message.len());
+            match parse(self.buf) {
+                Ok((message, len)) => {
+                    self.buf.advance(len);
                     Some(Ok(message))
                 }
                 // in case of an error we first try to advance the buffer to the start of the next message
                 // and then simply yield the error
                 Err(err) => {
                     if let Some(advance_by) = err.advance_by {
-                        self.buf.advance(advance_by);
+                        self.buf.advance(advance_by.min(self.buf.remaining()));
                         Some(Err(err))
                     } else {
                         // we use this instead of `memchr::memmem::find`
                         // because the malformed message could still have a valid `DLT\x01`
-                        // pattern and we would then try to parse the malformed message forever
-                        match memchr::memmem::find_iter(self.buf, b"DLT\x01")
-                            .find(|&index| index > 0)
-                        {
+                        // or `DLT\x02` pattern and we would then try to parse the malformed message forever
+                        match memchr::memmem::find_iter(self.buf, StorageHeader::PATTERN).find(
+                            |&index| {
+                                index > 0
+                                    && matches!(
+                                        self.buf.get(index + StorageHeader::PATTERN.len()),
+                                        Some(0x1 | 0x2)
+                                    )
+                            },
+                        ) {
                             Some(start) => {
                                 self.buf.advance(start);
                                 Some(Err(err))
@@ -50,8 +93,344 @@ impl<'a> Iterator for DltFile<'a> {
             }
         }
     }
+}
+
+impl<'a> Iterator for DltFile<'a> {
+    type Item = Result<DltMessage<'a>, DltError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let string_decoding = self.string_decoding;
+        let decoders = self.decoders;
+        self.next_message(|buf| {
+            let mut message = DltMessage::from_slice(buf)?;
+            message.payload.set_string_decoding(string_decoding);
+            if let Some(decoders) = decoders {
+                message.set_decoder(decoders.decoder(&message));
+            }
+            let len = message.len();
+            Ok((message, len))
+        })
+    }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         (0, Some(self.buf.remaining() / MIN_MESSAGE_LENGTH))
     }
 }
+
+/// An iterator over messages without storage headers, e.g. a raw network capture written by `nc`.
+///
+/// Messages are only delimited by the length in their standard header, so after a malformed message
+/// the next one is the first position where a plausible message starts: one with a valid standard header
+/// and a known message type, which is followed by another message.
+/// The messages get a synthesized storage header, see [`DltMessage::from_slice_without_storage_header`].
+#[derive(Debug)]
+pub struct DltStreamFile<'a> {
+    buf: &'a [u8],
+    string_decoding: StringDecoding,
+    decoders: Option<&'a DecoderRegistry>,
+}
+
+impl<'a> DltStreamFile<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self {
+            buf,
+            string_decoding: StringDecoding::default(),
+            decoders: None,
+        }
+    }
+
+    /// Sets how string arguments that are not valid UTF-8 are handled in all messages of this file
+    pub fn with_string_decoding(mut self, string_decoding: StringDecoding) -> Self {
+        self.string_decoding = string_decoding;
+        self
+    }
+
+    /// Sets the decoders that format the payloads of the messages of this file, see [`DltMessage::set_decoder`]
+    pub fn with_decoders(mut self, decoders: &'a DecoderRegistry) -> Self {
+        self.decoders = Some(decoders);
+        self
+    }
+}
+
+impl<'a> Iterator for DltStreamFile<'a> {
+    type Item = Result<DltMessage<'a>, DltError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.buf.has_remaining() {
+            return None;
+        }
+        match DltMessage::from_slice_without_storage_header(self.buf) {
+            Ok(mut message) => {
+                self.buf.advance(message.len());
+                message.payload.set_string_decoding(self.string_decoding);
+                if let Some(decoders) = self.decoders {
+                    message.set_decoder(decoders.decoder(&message));
+                }
+                Some(Ok(message))
+            }
+            Err(err) => {
+                if let Some(advance_by) = err.advance_by {
+                    self.buf.advance(advance_by.min(self.buf.remaining()));
+                    Some(Err(err))
+                } else {
+                    // like in `DltFile`, the malformed message itself must not be found again
+                    let start = (1..self.buf.len()).find(|&index| {
+                        DltMessage::plausible_raw_length(&self.buf[index..]).is_some()
+                    })?;
+                    self.buf.advance(start);
+                    Some(Err(err))
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.buf.remaining() / 4))
+    }
+}
+
+/// An iterator over the messages of both protocol versions in a DLT file
+#[cfg(feature = "r22-11")]
+#[derive(Debug)]
+pub struct VersionedDltFile<'a> {
+    file: DltFile<'a>,
+}
+
+#[cfg(feature = "r22-11")]
+impl<'a> Iterator for VersionedDltFile<'a> {
+    type Item = Result<VersionedMessage<'a>, DltError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let string_decoding = self.file.string_decoding;
+        let decoders = self.file.decoders;
+        self.file.next_message(|buf| {
+            let mut message = VersionedMessage::from_slice(buf)?;
+            message.set_string_decoding(string_decoding);
+            // decoders only know about version 1 messages
+            if let (Some(decoders), VersionedMessage::V1(message)) = (decoders, &mut message) {
+                message.set_decoder(decoders.decoder(message));
+            }
+            let len = message.len();
+            Ok((message, len))
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.file.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::error::ParseError;
+    use crate::header::{BusInfo, MessageTypeInfo};
+    use crate::payload::control::ServiceId;
+
+    // storage header, standard header with ECU id and timestamp, extended header
+    // and a verbose payload with a string, an array and a struct
+    const MESSAGE: &[u8] = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                             \x35\x01\x00\x36ECU1\x00\x00\x05\x39\
+                             \x41\x03APP1CTX1\
+                             \x00\x82\x00\x00\x03\x00hi\x00\
+                             \x42\x01\x00\x00\x01\x00\x02\x00\x01\x00\x02\x00\
+                             \x00\x40\x00\x00\x01\x00\x11\x00\x00\x00\x01";
+
+    /// Parses and formats everything, which must never panic
+    fn exhaust(data: &[u8]) -> usize {
+        DltFile::new(data)
+            .flatten()
+            .map(|message| {
+                let _ = message.to_string();
+                message.payload.as_verbose().map_or(0, |payload| {
+                    payload
+                        .arguments()
+                        .flatten()
+                        .inspect(|arg| {
+                            let _ = arg.to_string();
+                        })
+                        .count()
+                })
+            })
+            .sum()
+    }
+
+    #[test]
+    fn valid_messages() {
+        let data = [MESSAGE, MESSAGE].concat();
+        let mut file = DltFile::new(&data);
+        let message = file.next().unwrap().unwrap();
+        assert_eq!(message.len(), MESSAGE.len());
+        assert_eq!(message.payload.to_string(), "hi [1, 2] {true} ".to_string());
+        assert!(file.next().unwrap().is_ok());
+        assert!(file.next().is_none());
+    }
+
+    #[test]
+    fn truncated_messages() {
+        for end in 0..MESSAGE.len() {
+            exhaust(&MESSAGE[..end]);
+            exhaust(&[MESSAGE, &MESSAGE[..end], MESSAGE].concat());
+        }
+    }
+
+    #[test]
+    fn corrupted_messages() {
+        // a tiny xorshift generator, so the test is deterministic without any dependencies
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let original = [MESSAGE, MESSAGE, MESSAGE].concat();
+        for _ in 0..10_000 {
+            let mut data = original.clone();
+            for _ in 0..random() % 8 + 1 {
+                let index = random() as usize % data.len();
+                data[index] = random() as u8;
+            }
+            exhaust(&data);
+        }
+    }
+
+    #[test]
+    fn length_smaller_than_header() {
+        // the standard header claims to be 2 bytes long
+        let data = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\x20\x01\x00\x02";
+        assert!(matches!(
+            DltMessage::from_slice(data),
+            Err(DltError {
+                source: ParseError::InvalidLength(2),
+                ..
+            })
+        ));
+        assert_eq!(exhaust(data), 0);
+    }
+
+    #[test]
+    fn user_defined_message_type() {
+        let mut data = MESSAGE.to_vec();
+        // verbose network trace with the user defined bus type 0xa
+        data[28] = 0xa5;
+        let message = DltFile::new(&data).next().unwrap().unwrap();
+        assert_eq!(
+            message.type_info(),
+            Some(MessageTypeInfo::Bus(BusInfo::UserDefined(0xa)))
+        );
+        assert!(message
+            .to_string()
+            .contains(" ECU1 APP1 CTX1 nw_trace user_defined_10 verbose 3 hi "));
+    }
+
+    #[test]
+    fn control_message() {
+        // a get_software_version response from the dlt-daemon
+        let data = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                     \x21\x00\x00\x1a\
+                     \x26\x01DA1\x00DC1\x00\
+                     \x13\x00\x00\x00\x00\x03\x00\x00\x001.0";
+        let message = DltFile::new(data).next().unwrap().unwrap();
+        assert_eq!(
+            message.control_payload().unwrap().unwrap().service_id(),
+            ServiceId::GetSoftwareVersion
+        );
+        assert!(message
+            .to_string()
+            .ends_with(" DA1 DC1 control response non-verbose 1 [get_software_version ok] 1.0"));
+    }
+
+    #[test]
+    fn storage_header_v2() {
+        // the same message behind a version 2 storage header with a 5 character ECU id
+        let v2 = [
+            b"DLT\x02\x00\x00\x00\x00\x0d\x00\x00\x00\x00\x05ECU12",
+            &MESSAGE[16..],
+        ]
+        .concat();
+        let data = [MESSAGE, &v2, MESSAGE].concat();
+        let messages = DltFile::new(&data).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].storage_header.version(), 2);
+        assert_eq!(messages[1].storage_header.ecu_id, "ECU12");
+        assert_eq!(messages[1].len(), v2.len());
+
+        // resynchronise on the version 2 pattern after garbage
+        let data = [&b"garbage"[..], &v2].concat();
+        let mut file = DltFile::new(&data);
+        assert!(file.next().unwrap().is_err());
+        assert!(file.next().unwrap().is_ok());
+        assert!(file.next().is_none());
+    }
+
+    #[test]
+    fn stream_file() {
+        let raw = &MESSAGE[16..];
+        let message = DltMessage::from_slice_without_storage_header(raw).unwrap();
+        assert!(message.storage_header.is_synthesized());
+        assert_eq!(message.ecu_id(), "ECU1");
+        assert_eq!(message.len(), raw.len());
+        assert!(DltMessage::from_slice(raw).is_err());
+
+        // garbage that looks like the start of a message, two messages, garbage,
+        // and a message followed by a truncated one
+        let data = [
+            &b"\x20\x00\x00\x02\x35\x01"[..],
+            raw,
+            raw,
+            b"\x00\xff",
+            raw,
+            &raw[..20],
+        ]
+        .concat();
+        let results = DltStreamFile::new(&data)
+            .map(|message| message.map(|message| message.len()).ok())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            [
+                None,
+                Some(raw.len()),
+                Some(raw.len()),
+                None,
+                Some(raw.len())
+            ]
+        );
+
+        for end in 0..data.len() {
+            DltStreamFile::new(&data[..end]).for_each(drop);
+        }
+    }
+
+    #[test]
+    fn skip_version_2_messages() {
+        let v2 = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\x42\x00\x00\x00\x00\x00\x0d\x26\x00\x01\x00\x00\x00";
+        let data = [MESSAGE, v2, v2, MESSAGE].concat();
+        let mut file = DltFile::new(&data);
+        assert!(file.next().unwrap().is_ok());
+        for _ in 0..2 {
+            let err = file.next().unwrap().unwrap_err();
+            assert_eq!(err.advance_by, Some(v2.len()));
+            assert!(matches!(err.source, ParseError::Unsupported(_)));
+        }
+        assert!(file.next().unwrap().is_ok());
+        assert!(file.next().is_none());
+    }
+
+    #[cfg(feature = "r22-11")]
+    #[test]
+    fn versioned_messages() {
+        let v2 = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\x42\x00\x00\x00\x00\x00\x0d\x26\x00\x01\x00\x00\x00";
+        let data = [MESSAGE, v2, MESSAGE].concat();
+        assert_eq!(DltFile::new(&data).filter(Result::is_ok).count(), 2);
+
+        let mut file = DltFile::new(&data).versioned();
+        assert!(matches!(file.next(), Some(Ok(VersionedMessage::V1(_)))));
+        assert!(matches!(file.next(), Some(Ok(VersionedMessage::V2(_)))));
+        assert!(matches!(file.next(), Some(Ok(VersionedMessage::V1(_)))));
+        assert!(file.next().is_none());
+    }
+}
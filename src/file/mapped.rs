@@ -0,0 +1,87 @@
+//! Opening DLT files without reading them into memory first.
+
+use std::{fs::File, io, path::Path};
+
+use memmap2::Mmap;
+
+use super::DltFile;
+
+/// A memory-mapped DLT file, whose messages borrow directly from the map.
+///
+/// Only the pages that are actually parsed are read, so iterating can start right away,
+/// even for files that are much bigger than the available memory.
+#[derive(Debug)]
+pub struct MappedDltFile {
+    map: Mmap,
+}
+
+impl MappedDltFile {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the map is only ever read, but another process truncating or modifying the file
+        // while it is mapped can still make reads fault or change parsed data,
+        // so files that are still being written should be read with a `DltReader` instead
+        let map = unsafe { Mmap::map(&file)? };
+        // messages are parsed front to back, so the OS can read ahead aggressively
+        #[cfg(unix)]
+        map.advise(memmap2::Advice::Sequential)?;
+        Ok(Self { map })
+    }
+
+    /// Iterates over the messages, just like a [`DltFile`] over the whole content
+    pub fn messages(&self) -> DltFile<'_> {
+        DltFile::new(&self.map)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a MappedDltFile {
+    type Item = <DltFile<'a> as Iterator>::Item;
+    type IntoIter = DltFile<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.messages()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn open_file() {
+        let message = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                        \x21\x00\x00\x17\
+                        \x41\x01APP1CTX1\
+                        \x00\x82\x00\x00\x03\x00hi\x00";
+        let path = std::env::temp_dir().join(format!("fast-dlt-mapped-{}.dlt", std::process::id()));
+        File::create(&path)
+            .and_then(|mut file| file.write_all(&[&message[..], message].concat()))
+            .unwrap();
+
+        let file = MappedDltFile::open(&path).unwrap();
+        assert_eq!(file.len(), 2 * message.len());
+        let messages = file.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].payload.to_string(), "hi ");
+
+        drop(messages);
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+        assert!(MappedDltFile::open(&path).is_err());
+    }
+}
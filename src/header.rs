@@ -1,47 +1,109 @@
-use crate::{error::{DltError, ParseError}, get_str, get_slice};
-use std::str;
+use crate::{ensure_remaining, error::{DltError, ParseError}, get_str, get_slice};
+use std::{str, time::Duration};
 use bytes::Buf; 
 use simdutf8::basic::from_utf8;
 
+#[cfg(feature = "r22-11")]
+pub mod v2;
+
+/// The header that DLT loggers prepend to every message they store.
+///
+/// Version 1 (`DLT\x01`) has a 32 bit seconds field, microseconds and a 4 character ECU id,
+/// version 2 (`DLT\x02`) has a 40 bit seconds field, nanoseconds and a length-prefixed ECU id.
+/// Both are little endian.
 #[derive(Debug, PartialEq, Eq)]
 pub struct StorageHeader<'a> {
-    pub seconds: u32,
-    pub microseconds: i32,
+    version: u8,
+    pub seconds: u64,
+    pub nanoseconds: u32,
     pub ecu_id: &'a str,
+    length: usize,
 }
+
 impl<'a> StorageHeader<'a> {
 
-    const MIN_LENGTH: usize = 16;
+    /// The `DLT` pattern shared by all versions, followed by the version number
+    pub(crate) const PATTERN: &'static [u8] = b"DLT";
+    const V1_LENGTH: usize = 16;
+    const V2_MIN_LENGTH: usize = 4 /*DLT pattern*/ + 4 /*nanoseconds*/ + 5 /*seconds*/ + 1 /*ecu id length*/;
+    pub(crate) const MIN_LENGTH: usize = Self::V2_MIN_LENGTH;
 
     pub fn from_slice(mut buf: &'a [u8]) -> Result<Self, ParseError> {
 
-        if buf.remaining() < Self::MIN_LENGTH {
-            return Err(ParseError::NotEnoughData{needed: Self::MIN_LENGTH, available: buf.len()})
-        }
+        ensure_remaining!(buf, Self::PATTERN.len() + 1);
 
         // check for DLT pattern
-        if get_slice!(buf, 4) != b"DLT\x01" {
+        if get_slice!(buf, Self::PATTERN.len()) != Self::PATTERN {
             return Err(ParseError::MissingDltPattern)
         }
 
- 
-        // unwrapping is ok here, because we check if there's enough data ahead of this
-        let seconds = buf.get_u32_le();
-        let microseconds = buf.get_i32_le();
-        let ecu_id = get_str!(buf, 4)?.trim_end_matches('\0');
+        match buf.get_u8() {
+            0x1 => {
+                ensure_remaining!(buf, Self::V1_LENGTH - 4);
+                let seconds = buf.get_u32_le() as u64;
+                let microseconds = buf.get_u32_le();
+                let ecu_id = get_str!(buf, 4)?.trim_end_matches('\0');
+                Ok(Self {
+                    version: 1,
+                    seconds,
+                    nanoseconds: microseconds.saturating_mul(1000),
+                    ecu_id,
+                    length: Self::V1_LENGTH,
+                })
+            }
+            0x2 => {
+                ensure_remaining!(buf, Self::V2_MIN_LENGTH - 4);
+                let nanoseconds = buf.get_u32_le();
+                let seconds = buf.get_uint_le(5);
+                let length = buf.get_u8() as usize;
+                let ecu_id = get_str!(buf, length)?.trim_end_matches('\0');
+                Ok(Self {
+                    version: 2,
+                    seconds,
+                    nanoseconds,
+                    ecu_id,
+                    length: Self::V2_MIN_LENGTH + length,
+                })
+            }
+            _ => Err(ParseError::MissingDltPattern),
+        }
+    }
 
-        Ok(Self {
-            seconds,
-            microseconds,
+    /// A version 1 header for a message that was received without one, e.g. over the network.
+    /// It is not part of the message data, so its length is 0.
+    pub fn synthesized(timestamp: Duration, ecu_id: &'a str) -> Self {
+        Self {
+            version: 1,
+            seconds: timestamp.as_secs(),
+            nanoseconds: timestamp.subsec_nanos(),
             ecu_id,
-        })}
+            length: 0,
+        }
+    }
 
+    /// Whether this header was created by [`StorageHeader::synthesized`] instead of being parsed
+    pub fn is_synthesized(&self) -> bool {
+        self.length == 0
+    }
+
+    /// The version of the storage header, either 1 or 2
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// The sub-second part of the timestamp in microseconds, which is all that version 1 stores
+    pub fn microseconds(&self) -> u32 {
+        self.nanoseconds / 1000
+    }
 
+    /// The time at which the message was stored, with nanosecond precision, since the unix epoch
+    pub fn timestamp(&self) -> Duration {
+        Duration::from_secs(self.seconds) + Duration::from_nanos(self.nanoseconds as u64)
+    }
+
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
-        4 /*DLT pattern*/ 
-        + 4 /*seconds*/ 
-        + 4 /*microseconds*/ 
-        + 4 /*ecu id*/
+        self.length
     }
 }
 
@@ -69,7 +131,7 @@ pub struct StandardHeader<'a> {
 
 impl<'a> StandardHeader<'a> {
 
-    const MIN_LENGTH: usize = 4;
+    pub(crate) const MIN_LENGTH: usize = 4;
 
     pub fn from_slice(mut buf: &'a [u8]) -> Result<Self, DltError> {
         if buf.remaining() < Self::MIN_LENGTH {
@@ -137,6 +199,7 @@ impl<'a> StandardHeader<'a> {
         self.header_type & StdHeaderMask::MsbFirst as u8 != 0
     }
 
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         1 /*header type*/
         + 1 /*message_counter */
@@ -148,34 +211,41 @@ impl<'a> StandardHeader<'a> {
 }
 
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
 pub enum MessageType {
     Log = 0x0,
     AppTrace = 0x1,
     NwTrace = 0x2,
-    Control = 0x3
+    Control = 0x3,
+    Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
 pub enum LogInfo {
     Fatal = 0x1,
     Error = 0x2,
     Warn = 0x3,
     Info = 0x4,
     Debug = 0x5,
-    Verbose = 0x6
+    Verbose = 0x6,
+    Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
 pub enum TraceInfo {
     Variable = 0x1,
     FunctionIn = 0x2,
     FunctionOut = 0x3,
     State = 0x4,
-    Vfb = 0x5
+    Vfb = 0x5,
+    Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
 pub enum BusInfo {
     Ipc = 0x1,
     Can = 0x2,
@@ -183,24 +253,29 @@ pub enum BusInfo {
     Most = 0x4,
     Ethernet = 0x5,
     SomeIP = 0x6,
-    // UserDefined
-
+    /// 0x7 to 0xF are reserved for user defined bus types
+    UserDefined(u8),
+    Unknown(u8),
 }
 
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
 pub enum ControlInfo {
     Request = 0x1,
     Response = 0x2,
-    // Time = 0x3 ??
+    Time = 0x3,
+    Unknown(u8),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MessageTypeInfo {
     Log(LogInfo),
     Trace(TraceInfo),
     Bus(BusInfo),
-    Control(ControlInfo)
+    Control(ControlInfo),
+    /// The message type itself is unknown, so its info can not be interpreted either
+    Unknown { message_type: u8, info: u8 },
 }
 
 
@@ -240,41 +315,14 @@ impl<'a> ExtendedHeader<'a> {
     }
 
     pub fn message_type(&self) -> MessageType {
-        match (self.message_info & 0b00001110) >> 1 {
-            0x0 => MessageType::Log,
-            0x1 => MessageType::AppTrace,
-            0x2 => MessageType::NwTrace,
-            0x3 => MessageType::Control,
-            _ => unreachable!()
-        }
-
+        message_type(self.message_info)
     }
 
     pub fn type_info(&self) -> MessageTypeInfo {
-        match (self.message_type(), (self.message_info & 0b11110000) >> 4) {
-            (MessageType::Log, 0x1) => MessageTypeInfo::Log(LogInfo::Fatal),
-            (MessageType::Log, 0x2) => MessageTypeInfo::Log(LogInfo::Error),
-            (MessageType::Log, 0x3) => MessageTypeInfo::Log(LogInfo::Warn),
-            (MessageType::Log, 0x4) => MessageTypeInfo::Log(LogInfo::Info),
-            (MessageType::Log, 0x5) => MessageTypeInfo::Log(LogInfo::Debug),
-            (MessageType::Log, 0x6) => MessageTypeInfo::Log(LogInfo::Verbose),
-            (MessageType::AppTrace, 0x1) => MessageTypeInfo::Trace(TraceInfo::Variable),
-            (MessageType::AppTrace, 0x2) => MessageTypeInfo::Trace(TraceInfo::FunctionIn),
-            (MessageType::AppTrace, 0x3) => MessageTypeInfo::Trace(TraceInfo::FunctionOut),
-            (MessageType::AppTrace, 0x4) => MessageTypeInfo::Trace(TraceInfo::State),
-            (MessageType::AppTrace, 0x5) => MessageTypeInfo::Trace(TraceInfo::Vfb),
-            (MessageType::NwTrace, 0x1)=> MessageTypeInfo::Bus(BusInfo::Ipc),
-            (MessageType::NwTrace, 0x2)=> MessageTypeInfo::Bus(BusInfo::Can),
-            (MessageType::NwTrace, 0x3)=> MessageTypeInfo::Bus(BusInfo::Flexray),
-            (MessageType::NwTrace, 0x4)=> MessageTypeInfo::Bus(BusInfo::Most),
-            (MessageType::NwTrace, 0x5)=> MessageTypeInfo::Bus(BusInfo::Ethernet),
-            (MessageType::NwTrace, 0x6)=> MessageTypeInfo::Bus(BusInfo::SomeIP),
-            (MessageType::Control, 0x1) => MessageTypeInfo::Control(ControlInfo::Request),
-            (MessageType::Control, 0x2) => MessageTypeInfo::Control(ControlInfo::Response),
-            (message_type, info) => unreachable!("Unexpected: ({message_type:?}, {info})")
-        }
+        type_info(self.message_info)
     }
 
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         1 /*message_info*/
         + 1 /*number_of_arguments*/
@@ -283,6 +331,54 @@ impl<'a> ExtendedHeader<'a> {
     }
 }
 
+/// The message type encoded in a message info (MSIN) byte
+pub(crate) fn message_type(message_info: u8) -> MessageType {
+    match (message_info & 0b00001110) >> 1 {
+        0x0 => MessageType::Log,
+        0x1 => MessageType::AppTrace,
+        0x2 => MessageType::NwTrace,
+        0x3 => MessageType::Control,
+        other => MessageType::Unknown(other)
+    }
+}
+
+/// The message type info encoded in a message info (MSIN) byte
+pub(crate) fn type_info(message_info: u8) -> MessageTypeInfo {
+    match (message_type(message_info), (message_info & 0b11110000) >> 4) {
+        (MessageType::Log, 0x1) => MessageTypeInfo::Log(LogInfo::Fatal),
+        (MessageType::Log, 0x2) => MessageTypeInfo::Log(LogInfo::Error),
+        (MessageType::Log, 0x3) => MessageTypeInfo::Log(LogInfo::Warn),
+        (MessageType::Log, 0x4) => MessageTypeInfo::Log(LogInfo::Info),
+        (MessageType::Log, 0x5) => MessageTypeInfo::Log(LogInfo::Debug),
+        (MessageType::Log, 0x6) => MessageTypeInfo::Log(LogInfo::Verbose),
+        (MessageType::Log, info) => MessageTypeInfo::Log(LogInfo::Unknown(info)),
+        (MessageType::AppTrace, 0x1) => MessageTypeInfo::Trace(TraceInfo::Variable),
+        (MessageType::AppTrace, 0x2) => MessageTypeInfo::Trace(TraceInfo::FunctionIn),
+        (MessageType::AppTrace, 0x3) => MessageTypeInfo::Trace(TraceInfo::FunctionOut),
+        (MessageType::AppTrace, 0x4) => MessageTypeInfo::Trace(TraceInfo::State),
+        (MessageType::AppTrace, 0x5) => MessageTypeInfo::Trace(TraceInfo::Vfb),
+        (MessageType::AppTrace, info) => MessageTypeInfo::Trace(TraceInfo::Unknown(info)),
+        (MessageType::NwTrace, 0x1)=> MessageTypeInfo::Bus(BusInfo::Ipc),
+        (MessageType::NwTrace, 0x2)=> MessageTypeInfo::Bus(BusInfo::Can),
+        (MessageType::NwTrace, 0x3)=> MessageTypeInfo::Bus(BusInfo::Flexray),
+        (MessageType::NwTrace, 0x4)=> MessageTypeInfo::Bus(BusInfo::Most),
+        (MessageType::NwTrace, 0x5)=> MessageTypeInfo::Bus(BusInfo::Ethernet),
+        (MessageType::NwTrace, 0x6)=> MessageTypeInfo::Bus(BusInfo::SomeIP),
+        (MessageType::NwTrace, info @ 0x7..=0xf)=> MessageTypeInfo::Bus(BusInfo::UserDefined(info)),
+        (MessageType::NwTrace, info)=> MessageTypeInfo::Bus(BusInfo::Unknown(info)),
+        (MessageType::Control, 0x1) => MessageTypeInfo::Control(ControlInfo::Request),
+        (MessageType::Control, 0x2) => MessageTypeInfo::Control(ControlInfo::Response),
+        (MessageType::Control, 0x3) => MessageTypeInfo::Control(ControlInfo::Time),
+        (MessageType::Control, info) => MessageTypeInfo::Control(ControlInfo::Unknown(info)),
+        (MessageType::Unknown(message_type), info) => MessageTypeInfo::Unknown { message_type, info },
+    }
+}
+
+/// The protocol version in the header type of a standard header (or R22-11 base header)
+pub(crate) fn protocol_version(header_type: u8) -> u8 {
+    (header_type & StdHeaderMask::VersionNumber as u8) >> 5
+}
+
 #[cfg(test)]
 mod test {
 
@@ -295,11 +391,34 @@ mod test {
         assert_eq!(
             header,
             StorageHeader {
+                version: 1,
                 seconds: 13,
-                microseconds: 37,
-                ecu_id: "TEST"
+                nanoseconds: 37_000,
+                ecu_id: "TEST",
+                length: 16,
             }
         );
+        assert_eq!(header.microseconds(), 37);
+    }
+
+    #[test]
+    fn storage_header_v2() {
+        let bytes = b"DLT\x02\x15\xcd\x5b\x07\x0d\x00\x00\x00\x00\x05TEST1";
+        let header = StorageHeader::from_slice(bytes).unwrap();
+        assert_eq!(header.version(), 2);
+        assert_eq!(header.len(), bytes.len());
+        assert_eq!(header.ecu_id, "TEST1");
+        assert_eq!(header.microseconds(), 123_456);
+        assert_eq!(header.timestamp(), Duration::new(13, 123_456_789));
+
+        assert!(matches!(
+            StorageHeader::from_slice(&bytes[..bytes.len() - 1]),
+            Err(ParseError::NotEnoughData { .. })
+        ));
+        assert!(matches!(
+            StorageHeader::from_slice(b"DLT\x03\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00"),
+            Err(ParseError::MissingDltPattern)
+        ));
     }
 
     #[test]
@@ -332,4 +451,25 @@ mod test {
             }
         )
     }
+
+    #[test]
+    fn extended_header_unknown_message_info() {
+        let header = |message_info: u8| {
+            let bytes = [&[message_info, 0x07], &b"APPLCONT"[..]].concat();
+            ExtendedHeader::from_slice(&bytes).unwrap().type_info()
+        };
+        assert_eq!(header(0x70), MessageTypeInfo::Log(LogInfo::Unknown(0x7)));
+        assert_eq!(header(0x92), MessageTypeInfo::Trace(TraceInfo::Unknown(0x9)));
+        assert_eq!(header(0x04), MessageTypeInfo::Bus(BusInfo::Unknown(0x0)));
+        assert_eq!(header(0xa4), MessageTypeInfo::Bus(BusInfo::UserDefined(0xa)));
+        assert_eq!(header(0x36), MessageTypeInfo::Control(ControlInfo::Time));
+        assert_eq!(header(0x46), MessageTypeInfo::Control(ControlInfo::Unknown(0x4)));
+        assert_eq!(
+            header(0x4e),
+            MessageTypeInfo::Unknown {
+                message_type: 0x7,
+                info: 0x4
+            }
+        );
+    }
 }
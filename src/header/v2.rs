@@ -0,0 +1,397 @@
+//! The base header of the DLT protocol version 2, introduced with AUTOSAR R22-11.
+//!
+//! Unlike the standard header of version 1, the base header has a variable length:
+//! depending on the content of the message it carries the message info, timestamp or message id,
+//! followed by optional length-prefixed ids, tags, privacy level and segmentation information.
+
+use std::{fmt::Display, str};
+
+use bytes::Buf;
+use simdutf8::basic::from_utf8;
+
+use crate::{
+    ensure_remaining,
+    error::ParseError,
+    get_slice, get_str,
+    header::{message_type, type_info, MessageType, MessageTypeInfo},
+};
+
+#[rustfmt::skip]
+#[derive(Debug)]
+#[repr(u32)]
+enum BaseHeaderMask {
+    ContentInfo =           0b000000000011,
+    WithEcuId =             0b000000000100,
+    WithAppAndContextId =   0b000000001000,
+    WithSessionId =         0b000000010000,
+    VersionNumber =         0b000011100000,
+    WithSourceFileAndLine = 0b000100000000,
+    WithTags =              0b001000000000,
+    WithPrivacyLevel =      0b010000000000,
+    WithSegmentation =      0b100000000000,
+}
+
+/// What kind of message follows the base header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentInfo {
+    Verbose = 0x0,
+    NonVerbose = 0x1,
+    Control = 0x2,
+}
+
+/// A timestamp with nanosecond resolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp {
+    pub seconds: u64,
+    pub nanoseconds: u32,
+}
+
+impl Timestamp {
+    const LENGTH: usize = 9;
+
+    /// Reads the 32 bit nanoseconds followed by the 40 bit seconds
+    fn from_buf(buf: &mut &[u8]) -> Result<Self, ParseError> {
+        ensure_remaining!(buf, Self::LENGTH);
+        let nanoseconds = buf.get_u32();
+        let seconds = buf.get_uint(5);
+        Ok(Self {
+            seconds,
+            nanoseconds,
+        })
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:0>9}", self.seconds, self.nanoseconds)
+    }
+}
+
+/// Where a segment belongs in a message that was split into multiple segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segmentation {
+    FirstFrame { total_length: u64 },
+    ConsecutiveFrame { sequence_counter: u32 },
+    LastFrame,
+    AbortFrame { reason: u8 },
+}
+
+impl Segmentation {
+    fn from_buf(buf: &mut &[u8]) -> Result<Self, ParseError> {
+        ensure_remaining!(buf, 1);
+        match buf.get_u8() {
+            0x0 => {
+                ensure_remaining!(buf, 8);
+                Ok(Self::FirstFrame {
+                    total_length: buf.get_u64(),
+                })
+            }
+            0x1 => {
+                ensure_remaining!(buf, 4);
+                Ok(Self::ConsecutiveFrame {
+                    sequence_counter: buf.get_u32(),
+                })
+            }
+            0x2 => Ok(Self::LastFrame),
+            0x3 => {
+                ensure_remaining!(buf, 1);
+                Ok(Self::AbortFrame {
+                    reason: buf.get_u8(),
+                })
+            }
+            _ => Err(ParseError::Unsupported("segmentation frame type")),
+        }
+    }
+}
+
+/// The base header of a DLT version 2 message, including all of its optional fields
+#[derive(Debug, PartialEq, Eq)]
+pub struct BaseHeader<'a> {
+    header_type: u32,
+    pub message_counter: u8,
+    pub length: u16,
+    message_info: Option<u8>,
+    pub number_of_arguments: Option<u8>,
+    pub timestamp: Option<Timestamp>,
+    pub message_id: Option<u32>,
+    pub ecu_id: Option<&'a str>,
+    pub application_id: Option<&'a str>,
+    pub context_id: Option<&'a str>,
+    pub session_id: Option<u32>,
+    pub source_file: Option<&'a str>,
+    pub line_number: Option<u32>,
+    tags: Option<Tags<'a>>,
+    pub privacy_level: Option<u8>,
+    pub segmentation: Option<Segmentation>,
+    header_length: usize,
+}
+
+impl<'a> BaseHeader<'a> {
+    const MIN_LENGTH: usize = 4 /*header type*/ + 1 /*message counter*/ + 2 /*length*/;
+
+    pub fn from_slice(mut buf: &'a [u8]) -> Result<Self, ParseError> {
+        ensure_remaining!(buf, Self::MIN_LENGTH);
+
+        let available = buf.remaining();
+        // bit 0 of the header type is the least significant bit of the first byte,
+        // so that the version number is at the same position as in the standard header
+        let header_type = buf.get_u32_le();
+        let message_counter = buf.get_u8();
+        let length = buf.get_u16();
+
+        let content_info = match header_type & BaseHeaderMask::ContentInfo as u32 {
+            0x0 => ContentInfo::Verbose,
+            0x1 => ContentInfo::NonVerbose,
+            0x2 => ContentInfo::Control,
+            _ => return Err(ParseError::Unsupported("content info")),
+        };
+
+        let mut message_info = None;
+        let mut number_of_arguments = None;
+        let mut timestamp = None;
+        let mut message_id = None;
+        match content_info {
+            ContentInfo::Verbose => {
+                ensure_remaining!(buf, 2);
+                message_info = Some(buf.get_u8());
+                number_of_arguments = Some(buf.get_u8());
+                timestamp = Some(Timestamp::from_buf(&mut buf)?);
+            }
+            ContentInfo::NonVerbose => {
+                timestamp = Some(Timestamp::from_buf(&mut buf)?);
+                ensure_remaining!(buf, 4);
+                message_id = Some(buf.get_u32());
+            }
+            ContentInfo::Control => {
+                ensure_remaining!(buf, 2);
+                message_info = Some(buf.get_u8());
+                number_of_arguments = Some(buf.get_u8());
+            }
+        }
+
+        let with = |mask: BaseHeaderMask| header_type & mask as u32 != 0;
+
+        let ecu_id = if with(BaseHeaderMask::WithEcuId) {
+            Some(read_str(&mut buf)?)
+        } else {
+            None
+        };
+
+        let (application_id, context_id) = if with(BaseHeaderMask::WithAppAndContextId) {
+            (Some(read_str(&mut buf)?), Some(read_str(&mut buf)?))
+        } else {
+            (None, None)
+        };
+
+        let session_id = if with(BaseHeaderMask::WithSessionId) {
+            ensure_remaining!(buf, 4);
+            Some(buf.get_u32())
+        } else {
+            None
+        };
+
+        let (source_file, line_number) = if with(BaseHeaderMask::WithSourceFileAndLine) {
+            let source_file = read_str(&mut buf)?;
+            ensure_remaining!(buf, 4);
+            (Some(source_file), Some(buf.get_u32()))
+        } else {
+            (None, None)
+        };
+
+        let tags = if with(BaseHeaderMask::WithTags) {
+            Some(Tags::from_buf(&mut buf)?)
+        } else {
+            None
+        };
+
+        let privacy_level = if with(BaseHeaderMask::WithPrivacyLevel) {
+            ensure_remaining!(buf, 1);
+            Some(buf.get_u8())
+        } else {
+            None
+        };
+
+        let segmentation = if with(BaseHeaderMask::WithSegmentation) {
+            Some(Segmentation::from_buf(&mut buf)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            header_type,
+            message_counter,
+            length,
+            message_info,
+            number_of_arguments,
+            timestamp,
+            message_id,
+            ecu_id,
+            application_id,
+            context_id,
+            session_id,
+            source_file,
+            line_number,
+            tags,
+            privacy_level,
+            segmentation,
+            header_length: available - buf.remaining(),
+        })
+    }
+
+    pub fn content_info(&self) -> ContentInfo {
+        match self.header_type & BaseHeaderMask::ContentInfo as u32 {
+            0x0 => ContentInfo::Verbose,
+            0x1 => ContentInfo::NonVerbose,
+            // anything else is rejected in `from_slice`
+            _ => ContentInfo::Control,
+        }
+    }
+
+    pub fn verbose(&self) -> bool {
+        self.content_info() == ContentInfo::Verbose
+    }
+
+    pub fn version(&self) -> u8 {
+        ((self.header_type & BaseHeaderMask::VersionNumber as u32) >> 5) as u8
+    }
+
+    /// The message type, only available for verbose and control messages
+    pub fn message_type(&self) -> Option<MessageType> {
+        self.message_info.map(message_type)
+    }
+
+    /// The message type info, only available for verbose and control messages
+    pub fn type_info(&self) -> Option<MessageTypeInfo> {
+        self.message_info.map(type_info)
+    }
+
+    pub fn tags(&self) -> Tags<'a> {
+        self.tags.clone().unwrap_or_default()
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.header_length
+    }
+}
+
+/// Reads a string with a 8 bit length prefix
+fn read_str<'a>(buf: &mut &'a [u8]) -> Result<&'a str, ParseError> {
+    ensure_remaining!(buf, 1);
+    let length = buf.get_u8() as usize;
+    Ok(get_str!(buf, length)?.trim_end_matches('\0'))
+}
+
+/// An iterator over the tags of a [`BaseHeader`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tags<'a> {
+    data: &'a [u8],
+    number_of_tags: u8,
+}
+
+impl<'a> Tags<'a> {
+    fn from_buf(buf: &mut &'a [u8]) -> Result<Self, ParseError> {
+        ensure_remaining!(buf, 1);
+        let number_of_tags = buf.get_u8();
+
+        // the tags have to be read once to find out where they end and to validate them
+        let mut tags = *buf;
+        for _ in 0..number_of_tags {
+            read_str(&mut tags)?;
+        }
+        let data = get_slice!(buf, buf.remaining() - tags.remaining());
+
+        Ok(Self {
+            data,
+            number_of_tags,
+        })
+    }
+}
+
+impl<'a> Iterator for Tags<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.number_of_tags == 0 {
+            return None;
+        }
+        self.number_of_tags -= 1;
+        // the tags were already validated in `from_buf`
+        read_str(&mut self.data).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::header::LogInfo;
+
+    #[test]
+    fn verbose_base_header() {
+        // verbose, with ECU id, app and context id, version 2, tags and privacy level
+        let bytes = b"\x4c\x06\x00\x00\x2a\x00\x40\
+                      \x41\x02\x00\x00\x00\x07\x00\x00\x00\x00\x0d\
+                      \x05ECU1\x00\x04APP\x00\x04CTX\x00\
+                      \x02\x02a\x00\x03bc\x00\
+                      \x01";
+        let header = BaseHeader::from_slice(bytes).unwrap();
+        assert_eq!(header.len(), bytes.len());
+        assert_eq!(header.version(), 2);
+        assert_eq!(header.message_counter, 42);
+        assert_eq!(header.length, 64);
+        assert!(header.verbose());
+        assert_eq!(
+            header.type_info(),
+            Some(MessageTypeInfo::Log(LogInfo::Info))
+        );
+        assert_eq!(header.number_of_arguments, Some(2));
+        assert_eq!(
+            header.timestamp,
+            Some(Timestamp {
+                seconds: 13,
+                nanoseconds: 7
+            })
+        );
+        assert_eq!(header.ecu_id, Some("ECU1"));
+        assert_eq!(header.application_id, Some("APP"));
+        assert_eq!(header.context_id, Some("CTX"));
+        assert_eq!(header.tags().collect::<Vec<_>>(), ["a", "bc"]);
+        assert_eq!(header.privacy_level, Some(1));
+        assert_eq!(header.session_id, None);
+        assert_eq!(header.segmentation, None);
+    }
+
+    #[test]
+    fn non_verbose_base_header() {
+        // non-verbose, with session id, version 2, source file and line number and segmentation
+        let bytes = b"\x51\x09\x00\x00\x07\x00\x2b\
+                      \x00\x00\x00\x00\x00\x00\x00\x00\x01\
+                      \x00\x00\x04\xd2\
+                      \x00\x00\x00\x03\
+                      \x05main\x00\x00\x00\x00\x2a\
+                      \x00\x00\x00\x00\x00\x00\x00\x10\x00";
+        let header = BaseHeader::from_slice(bytes).unwrap();
+        assert_eq!(header.len(), bytes.len());
+        assert_eq!(header.content_info(), ContentInfo::NonVerbose);
+        assert_eq!(header.type_info(), None);
+        assert_eq!(header.message_id, Some(1234));
+        assert_eq!(header.session_id, Some(3));
+        assert_eq!(header.source_file, Some("main"));
+        assert_eq!(header.line_number, Some(42));
+        assert_eq!(
+            header.segmentation,
+            Some(Segmentation::FirstFrame { total_length: 4096 })
+        );
+    }
+
+    #[test]
+    fn truncated_base_header() {
+        let bytes =
+            b"\x44\x00\x00\x00\x00\x00\x20\x41\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x05EC";
+        assert!(matches!(
+            BaseHeader::from_slice(bytes),
+            Err(ParseError::NotEnoughData { .. })
+        ));
+    }
+}
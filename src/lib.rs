@@ -1,18 +1,32 @@
-// Release R22-11 Breaks backward-compatibility, TODO use #[cfg(feature = "r22-11")] to have both versions at the same time
+// Release R22-11 Breaks backward-compatibility, its version 2 messages are parsed with the "r22-11" feature
 // https://www.autosar.org/fileadmin/standards/foundation/22-11/AUTOSAR_PRS_LogAndTraceProtocol.pdf
 
 // Currently targeting release R20-11
 // https://www.autosar.org/fileadmin/standards/R20-11/FO/AUTOSAR_PRS_LogAndTraceProtocol.pdf
 
 #![warn(missing_debug_implementations, rust_2018_idioms)]
+pub mod catalog;
+pub mod client;
+pub mod decoder;
 pub mod error;
 pub mod file;
 pub mod header;
 pub mod message;
+pub mod parser;
 pub mod payload;
+pub mod reader;
+pub mod registry;
+#[cfg(feature = "tokio")]
+pub mod stream;
+pub mod udp;
 
-pub use file::DltFile;
+pub use file::{DltFile, DltStreamFile};
 pub use message::DltMessage;
+pub use parser::DltParser;
+pub use reader::DltReader;
+pub use registry::EcuRegistry;
+#[cfg(feature = "tokio")]
+pub use stream::DltStream;
 
 // TODO: use Cow<'a, str> everywhere?
 
@@ -43,3 +57,27 @@ macro_rules! get_slice {
         ret
     }};
 }
+
+#[macro_export]
+macro_rules! ensure_remaining {
+    ($buf: expr, $len: expr) => {{
+        if $buf.remaining() < $len {
+            return Err(ParseError::NotEnoughData {
+                needed: $len,
+                available: $buf.remaining(),
+            }
+            .into());
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! msb {
+    ($msb_first: expr, $be: expr, $le: expr) => {{
+        if $msb_first {
+            $be
+        } else {
+            $le
+        }
+    }};
+}
@@ -1,16 +1,20 @@
-use std::fmt::Display;
+use std::{fmt::Display, time::Duration};
 
 use bytes::Buf;
 
 use crate::{
-    error::DltError,
+    decoder::PayloadDecoder,
+    error::{DltError, ParseError},
     header::{
-        BusInfo, ControlInfo, ExtendedHeader, LogInfo, MessageType, MessageTypeInfo,
-        StandardHeader, StorageHeader, TraceInfo,
+        protocol_version, BusInfo, ControlInfo, ExtendedHeader, LogInfo, MessageType,
+        MessageTypeInfo, StandardHeader, StorageHeader, TraceInfo,
     },
-    payload::{NonVerbosePayload, Payload, VerbosePayload},
+    payload::{control::ControlPayload, NonVerbosePayload, Payload, VerbosePayload},
 };
 
+#[cfg(feature = "r22-11")]
+pub mod v2;
+
 #[derive(Debug)]
 pub struct DltMessage<'a> {
     pub storage_header: StorageHeader<'a>,
@@ -18,62 +22,203 @@ pub struct DltMessage<'a> {
     pub extended_header: Option<ExtendedHeader<'a>>,
     pub payload: Payload<'a>,
     source: &'a [u8],
+    decoder: Option<&'a dyn PayloadDecoder>,
 }
 
 impl<'a> DltMessage<'a> {
-    pub fn from_slice(mut buf: &'a [u8]) -> Result<Self, DltError> {
-        let source = buf;
+    pub fn from_slice(buf: &'a [u8]) -> Result<Self, DltError> {
+        let storage_header = StorageHeader::from_slice(buf)?;
+        Self::with_storage_header(storage_header, buf)
+    }
+
+    /// Parses a message without storage header, e.g. from a raw network capture.
+    /// It gets a synthesized storage header without a timestamp, and with the ECU id of the standard
+    /// header if there is one, see [`StorageHeader::synthesized`].
+    pub fn from_slice_without_storage_header(buf: &'a [u8]) -> Result<Self, DltError> {
+        Self::from_raw_slice(buf, Duration::ZERO, "")
+    }
+
+    /// Parses a message without storage header, as it is sent over the network.
+    /// It gets a synthesized one with the ECU id of the standard header, or `ecu_id` if there is none.
+    pub(crate) fn from_raw_slice(
+        buf: &'a [u8],
+        timestamp: Duration,
+        ecu_id: &'a str,
+    ) -> Result<Self, DltError> {
+        let standard_header = Self::raw_standard_header(buf)?;
+        let storage_header =
+            StorageHeader::synthesized(timestamp, standard_header.ecu_id.unwrap_or(ecu_id));
+        Self::with_storage_header(storage_header, buf)
+    }
+
+    /// The length of the message at the start of `buf`, for which only its storage and standard header
+    /// are parsed. Errors are the ones of [`DltMessage::from_slice`] that occur before the length is known,
+    /// all others only occur once the message is parsed.
+    pub(crate) fn length(buf: &'a [u8]) -> Result<usize, DltError> {
         let storage_header = StorageHeader::from_slice(buf)?;
-        buf.advance(storage_header.len());
+        Self::frame(storage_header.len(), buf).map(|(_, message_length)| message_length)
+    }
+
+    /// The length of the message without storage header at the start of `buf`, like [`DltMessage::length`]
+    pub(crate) fn raw_length(buf: &'a [u8]) -> Result<usize, DltError> {
+        Self::raw_standard_header(buf)?;
+        Self::frame(0, buf).map(|(_, message_length)| message_length)
+    }
+
+    /// The length of the message without storage header at the start of `buf`, if it looks like one.
+    /// This tells where the next message starts after a malformed one, when there is no `DLT` pattern.
+    ///
+    /// Its standard header has to be valid with protocol version 1, its message type has to be known
+    /// and the next message has to start right after it, unless `buf` ends there or in the next message.
+    pub(crate) fn plausible_raw_length(buf: &[u8]) -> Option<usize> {
+        fn message_length(buf: &[u8]) -> Result<usize, DltError> {
+            let message = DltMessage::from_slice_without_storage_header(buf)?;
+            if let Some(MessageType::Unknown(_)) = message.message_type() {
+                return Err(ParseError::Unsupported("unknown message type").into());
+            }
+            Ok(message.len())
+        }
+
+        let length = message_length(buf).ok()?;
+        match message_length(&buf[length..]) {
+            Err(DltError {
+                advance_by: None,
+                source,
+            }) if !matches!(source, ParseError::NotEnoughData { .. }) => None,
+            // the next message is framed correctly, even if its content is not, or `buf` ends in it
+            _ => Some(length),
+        }
+    }
+
+    fn raw_standard_header(buf: &'a [u8]) -> Result<StandardHeader<'a>, DltError> {
+        // without a storage header pattern, the version is the only hint
+        // that this is really the start of a message
+        if buf
+            .first()
+            .is_some_and(|&header_type| protocol_version(header_type) != 1)
+        {
+            return Err(ParseError::Unsupported("DLT protocol version other than 1").into());
+        }
+        StandardHeader::from_slice(buf)
+    }
+
+    /// Parses the standard header after a storage header of `storage_header_length` bytes
+    /// and returns it with the length of the whole message, which has to be part of `source`
+    fn frame(
+        storage_header_length: usize,
+        source: &'a [u8],
+    ) -> Result<(StandardHeader<'a>, usize), DltError> {
+        let buf = &source[storage_header_length..];
+
+        // version 2 has an entirely different header layout, which needs `v2::DltMessageV2`,
+        // but its length is always in the same place so it can still be skipped
+        if buf
+            .first()
+            .map(|&header_type| protocol_version(header_type))
+            == Some(2)
+        {
+            return Err(Self::skip_version_2(storage_header_length, source));
+        }
 
         let standard_header = StandardHeader::from_slice(buf)?;
-        buf.advance(standard_header.len());
+        // the length includes the standard header itself, anything smaller can not be right
+        // and there is no way to tell where the next message starts
+        if (standard_header.length as usize) < standard_header.len() {
+            return Err(ParseError::InvalidLength(standard_header.length).into());
+        }
+        let message_length = storage_header_length + standard_header.length as usize;
+        if message_length > source.len() {
+            return Err(ParseError::NotEnoughData {
+                needed: message_length,
+                available: source.len(),
+            }
+            .into());
+        }
+        Ok((standard_header, message_length))
+    }
+
+    /// Parses the rest of the message, `source` starts with the bytes of `storage_header`,
+    /// if it is not synthesized
+    pub(crate) fn with_storage_header(
+        storage_header: StorageHeader<'a>,
+        source: &'a [u8],
+    ) -> Result<Self, DltError> {
+        let (standard_header, message_length) = Self::frame(storage_header.len(), source)?;
+
+        // from here on errors are confined to this message, so we can skip to the next one
+        let skip_message = move |err| DltError {
+            advance_by: Some(message_length),
+            source: err,
+        };
+        let mut buf = &source[storage_header.len() + standard_header.len()..message_length];
 
         let extended_header = if standard_header.use_extended_header() {
-            let extended_header = ExtendedHeader::from_slice(buf).map_err(|err| DltError {
-                advance_by: Some(storage_header.len() + standard_header.length as usize),
-                source: err,
-            })?;
+            let extended_header = ExtendedHeader::from_slice(buf).map_err(skip_message)?;
             buf.advance(extended_header.len());
             Some(extended_header)
         } else {
             None
         };
 
-        let payload_length = standard_header.length as usize
-            - standard_header.len()
-            - extended_header.as_ref().map_or(0, ExtendedHeader::len);
+        let payload_length = buf.remaining();
         let payload = if extended_header
             .as_ref()
-            .map_or(false, ExtendedHeader::verbose)
+            .is_some_and(ExtendedHeader::verbose)
         {
             Payload::Verbose(
                 VerbosePayload::from_slice(buf, payload_length, standard_header.msb_first())
-                    .map_err(|err| DltError {
-                        advance_by: Some(storage_header.len() + standard_header.length as usize),
-                        source: err,
-                    })?,
+                    .map_err(skip_message)?,
             )
         } else {
             Payload::NonVerbose(
                 NonVerbosePayload::from_slice(buf, payload_length, standard_header.msb_first())
-                    .map_err(|err| DltError {
-                        advance_by: Some(storage_header.len() + standard_header.length as usize),
-                        source: err,
-                    })?,
+                    .map_err(skip_message)?,
             )
         };
 
         Ok(DltMessage {
-            source: &source[..storage_header.len() + standard_header.length as usize],
+            source: &source[..message_length],
             storage_header,
             standard_header,
             extended_header,
             payload,
+            decoder: None,
         })
     }
 
+    /// The error for a version 2 message after a storage header of `storage_header_length` bytes,
+    /// which advances past it if it is complete
+    fn skip_version_2(storage_header_length: usize, source: &[u8]) -> DltError {
+        // header type, message counter and the length, which includes the base header itself
+        const MIN_BASE_HEADER_LENGTH: usize = 4 + 1 + 2;
+        let Some(length) = source.get(storage_header_length + 5..storage_header_length + 7) else {
+            return ParseError::NotEnoughData {
+                needed: storage_header_length + MIN_BASE_HEADER_LENGTH,
+                available: source.len(),
+            }
+            .into();
+        };
+        let length = u16::from_be_bytes([length[0], length[1]]) as usize;
+        let message_length = storage_header_length + length;
+        let advance_by = if length < MIN_BASE_HEADER_LENGTH {
+            None
+        } else if message_length > source.len() {
+            return ParseError::NotEnoughData {
+                needed: message_length,
+                available: source.len(),
+            }
+            .into();
+        } else {
+            Some(message_length)
+        };
+        DltError {
+            advance_by,
+            source: ParseError::Unsupported("DLT protocol version 2"),
+        }
+    }
+
     // The length of the message in bytes
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         self.storage_header.len() + self.standard_header.length as usize
     }
@@ -81,6 +226,14 @@ impl<'a> DltMessage<'a> {
     pub fn ecu_id(&self) -> &str {
         self.storage_header.ecu_id
     }
+
+    /// The id of the ECU that sent the message, which is the one of the standard header if it has one.
+    /// The storage header can have the id of the logger instead, e.g. behind a gateway.
+    pub fn sender_ecu_id(&self) -> &str {
+        self.standard_header
+            .ecu_id
+            .unwrap_or(self.storage_header.ecu_id)
+    }
     pub fn app_id(&self) -> Option<&str> {
         self.extended_header
             .as_ref()
@@ -105,7 +258,21 @@ impl<'a> DltMessage<'a> {
     pub fn verbose(&self) -> bool {
         self.extended_header
             .as_ref()
-            .map_or(false, ExtendedHeader::verbose)
+            .is_some_and(ExtendedHeader::verbose)
+    }
+
+    /// The decoded payload of a control request or response
+    pub fn control_payload(&self) -> Option<Result<ControlPayload<'a>, ParseError>> {
+        let Some(MessageTypeInfo::Control(info)) = self.type_info() else {
+            return None;
+        };
+        let payload = self.payload.as_non_verbose()?;
+        ControlPayload::from_slice(
+            info,
+            payload.message_id(),
+            payload.data(),
+            self.standard_header.msb_first(),
+        )
     }
 
     pub fn timestamp(&self) -> Option<u32> {
@@ -115,13 +282,64 @@ impl<'a> DltMessage<'a> {
     pub fn as_bytes(&self) -> &[u8] {
         self.source
     }
+
+    /// Sets the decoder that formats the payload when displaying this message
+    pub fn set_decoder(&mut self, decoder: Option<&'a dyn PayloadDecoder>) {
+        self.decoder = decoder;
+    }
+
+    pub fn decoder(&self) -> Option<&'a dyn PayloadDecoder> {
+        self.decoder
+    }
+
+    pub(crate) fn fmt_type_info(
+        f: &mut std::fmt::Formatter<'_>,
+        type_info: MessageTypeInfo,
+    ) -> std::fmt::Result {
+        match type_info {
+            MessageTypeInfo::Log(LogInfo::Fatal) => write!(f, "log fatal "),
+            MessageTypeInfo::Log(LogInfo::Error) => write!(f, "log error "),
+            MessageTypeInfo::Log(LogInfo::Warn) => write!(f, "log warn "),
+            MessageTypeInfo::Log(LogInfo::Info) => write!(f, "log info "),
+            MessageTypeInfo::Log(LogInfo::Debug) => write!(f, "log debug "),
+            MessageTypeInfo::Log(LogInfo::Verbose) => write!(f, "log verbose "),
+            MessageTypeInfo::Log(LogInfo::Unknown(info)) => write!(f, "log {info} "),
+            MessageTypeInfo::Trace(TraceInfo::Variable) => write!(f, "app_trace variable "),
+            MessageTypeInfo::Trace(TraceInfo::FunctionIn) => write!(f, "app_trace func_in "),
+            MessageTypeInfo::Trace(TraceInfo::FunctionOut) => write!(f, "app_trace func_out "),
+            MessageTypeInfo::Trace(TraceInfo::State) => write!(f, "app_trace state "),
+            MessageTypeInfo::Trace(TraceInfo::Vfb) => write!(f, "app_trace vfb "),
+            MessageTypeInfo::Trace(TraceInfo::Unknown(info)) => {
+                write!(f, "app_trace {info} ")
+            }
+            MessageTypeInfo::Bus(BusInfo::Ipc) => write!(f, "nw_trace ipc "),
+            MessageTypeInfo::Bus(BusInfo::Can) => write!(f, "nw_trace can "),
+            MessageTypeInfo::Bus(BusInfo::Flexray) => write!(f, "nw_trace flexray "),
+            MessageTypeInfo::Bus(BusInfo::Most) => write!(f, "nw_trace most "),
+            MessageTypeInfo::Bus(BusInfo::Ethernet) => write!(f, "nw_trace ethernet "),
+            MessageTypeInfo::Bus(BusInfo::SomeIP) => write!(f, "nw_trace some_ip "),
+            MessageTypeInfo::Bus(BusInfo::UserDefined(info)) => {
+                write!(f, "nw_trace user_defined_{info} ")
+            }
+            MessageTypeInfo::Bus(BusInfo::Unknown(info)) => write!(f, "nw_trace {info} "),
+            MessageTypeInfo::Control(ControlInfo::Request) => write!(f, "control request "),
+            MessageTypeInfo::Control(ControlInfo::Response) => write!(f, "control response "),
+            MessageTypeInfo::Control(ControlInfo::Time) => write!(f, "control time "),
+            MessageTypeInfo::Control(ControlInfo::Unknown(info)) => {
+                write!(f, "control {info} ")
+            }
+            MessageTypeInfo::Unknown { message_type, info } => {
+                write!(f, "unknown_{message_type} {info} ")
+            }
+        }
+    }
 }
 
 impl<'a> Display for DltMessage<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Ok(dt) = speedate::DateTime::from_timestamp(
             self.storage_header.seconds as i64,
-            self.storage_header.microseconds as u32,
+            self.storage_header.microseconds(),
         ) {
             write!(
                 f,
@@ -136,11 +354,7 @@ impl<'a> Display for DltMessage<'a> {
 
         write!(f, "{:0>3} ", self.standard_header.message_counter)?;
 
-        if let Some(ecu_id) = self.standard_header.ecu_id {
-            write!(f, "{ecu_id} ")?;
-        } else {
-            write!(f, "{} ", self.storage_header.ecu_id)?;
-        }
+        write!(f, "{} ", self.sender_ecu_id())?;
 
         if let Some(ref ext_hdr) = self.extended_header {
             write!(f, "{} {} ", ext_hdr.application_id, ext_hdr.context_id)?;
@@ -151,27 +365,7 @@ impl<'a> Display for DltMessage<'a> {
         }
 
         if let Some(ref ext_hdr) = self.extended_header {
-            match ext_hdr.type_info() {
-                MessageTypeInfo::Log(LogInfo::Fatal) => write!(f, "log fatal "),
-                MessageTypeInfo::Log(LogInfo::Error) => write!(f, "log error "),
-                MessageTypeInfo::Log(LogInfo::Warn) => write!(f, "log warn "),
-                MessageTypeInfo::Log(LogInfo::Info) => write!(f, "log info "),
-                MessageTypeInfo::Log(LogInfo::Debug) => write!(f, "log debug "),
-                MessageTypeInfo::Log(LogInfo::Verbose) => write!(f, "log verbose "),
-                MessageTypeInfo::Trace(TraceInfo::Variable) => write!(f, "app_trace variable "),
-                MessageTypeInfo::Trace(TraceInfo::FunctionIn) => write!(f, "app_trace func_in "),
-                MessageTypeInfo::Trace(TraceInfo::FunctionOut) => write!(f, "app_trace func_out "),
-                MessageTypeInfo::Trace(TraceInfo::State) => write!(f, "app_trace state "),
-                MessageTypeInfo::Trace(TraceInfo::Vfb) => write!(f, "app_trace vfb "),
-                MessageTypeInfo::Bus(BusInfo::Ipc) => write!(f, "nw_trace ipc "),
-                MessageTypeInfo::Bus(BusInfo::Can) => write!(f, "nw_trace can "),
-                MessageTypeInfo::Bus(BusInfo::Flexray) => write!(f, "nw_trace flexray "),
-                MessageTypeInfo::Bus(BusInfo::Most) => write!(f, "nw_trace most "),
-                MessageTypeInfo::Bus(BusInfo::Ethernet) => write!(f, "nw_trace ethernet "),
-                MessageTypeInfo::Bus(BusInfo::SomeIP) => write!(f, "nw_trace some_ip "),
-                MessageTypeInfo::Control(ControlInfo::Request) => write!(f, "control request "),
-                MessageTypeInfo::Control(ControlInfo::Response) => write!(f, "control response "),
-            }?;
+            Self::fmt_type_info(f, ext_hdr.type_info())?;
         }
 
         if let Some(ref ext_hdr) = self.extended_header {
@@ -188,7 +382,14 @@ impl<'a> Display for DltMessage<'a> {
             write!(f, "{} ", ext_hdr.number_of_arguments)?;
         }
 
-        write!(f, "{}", self.payload)?;
+        if let Some(decoder) = self.decoder {
+            decoder.fmt(self, f)?;
+        } else {
+            match self.control_payload() {
+                Some(Ok(control)) => write!(f, "{control}")?,
+                _ => write!(f, "{}", self.payload)?,
+            }
+        }
 
         Ok(())
     }
@@ -0,0 +1,284 @@
+//! Messages of the DLT protocol version 2, introduced with AUTOSAR R22-11.
+
+use std::fmt::Display;
+
+use bytes::Buf;
+
+use crate::{
+    error::{DltError, ParseError},
+    header::{protocol_version, v2::BaseHeader, v2::ContentInfo, MessageTypeInfo, StorageHeader},
+    message::DltMessage,
+    payload::{
+        control::ControlPayload, NonVerbosePayload, Payload, StringDecoding, VerbosePayload,
+    },
+};
+
+#[derive(Debug)]
+pub struct DltMessageV2<'a> {
+    pub storage_header: StorageHeader<'a>,
+    pub base_header: BaseHeader<'a>,
+    pub payload: Payload<'a>,
+    source: &'a [u8],
+}
+
+impl<'a> DltMessageV2<'a> {
+    pub fn from_slice(mut buf: &'a [u8]) -> Result<Self, DltError> {
+        let source = buf;
+        let storage_header = StorageHeader::from_slice(buf)?;
+        buf.advance(storage_header.len());
+
+        let base_header = BaseHeader::from_slice(buf)?;
+        if base_header.version() != 2 {
+            return Err(ParseError::Unsupported("DLT protocol version").into());
+        }
+        // the length includes the base header itself, anything smaller can not be right
+        // and there is no way to tell where the next message starts
+        if (base_header.length as usize) < base_header.len() {
+            return Err(ParseError::InvalidLength(base_header.length).into());
+        }
+        let message_length = storage_header.len() + base_header.length as usize;
+        if message_length > source.len() {
+            return Err(ParseError::NotEnoughData {
+                needed: message_length,
+                available: source.len(),
+            }
+            .into());
+        }
+
+        // from here on errors are confined to this message, so we can skip to the next one
+        let skip_message = move |err| DltError {
+            advance_by: Some(message_length),
+            source: err,
+        };
+        let buf = &source[storage_header.len() + base_header.len()..message_length];
+
+        // there is no byte order flag anymore, so the payload is always little endian
+        let payload = match (base_header.content_info(), base_header.message_id) {
+            (ContentInfo::Verbose, _) => Payload::Verbose(
+                VerbosePayload::from_slice(buf, buf.len(), false).map_err(skip_message)?,
+            ),
+            (ContentInfo::NonVerbose, Some(message_id)) => {
+                Payload::NonVerbose(NonVerbosePayload::with_message_id(message_id, buf))
+            }
+            // control messages still start with their service id
+            _ => Payload::NonVerbose(
+                NonVerbosePayload::from_slice(buf, buf.len(), false).map_err(skip_message)?,
+            ),
+        };
+
+        Ok(Self {
+            source: &source[..message_length],
+            storage_header,
+            base_header,
+            payload,
+        })
+    }
+
+    // The length of the message in bytes
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.storage_header.len() + self.base_header.length as usize
+    }
+
+    pub fn ecu_id(&self) -> &str {
+        self.base_header
+            .ecu_id
+            .unwrap_or(self.storage_header.ecu_id)
+    }
+    pub fn app_id(&self) -> Option<&str> {
+        self.base_header.application_id
+    }
+    pub fn context_id(&self) -> Option<&str> {
+        self.base_header.context_id
+    }
+
+    pub fn type_info(&self) -> Option<MessageTypeInfo> {
+        self.base_header.type_info()
+    }
+
+    pub fn verbose(&self) -> bool {
+        self.base_header.verbose()
+    }
+
+    /// The decoded payload of a control request or response
+    pub fn control_payload(&self) -> Option<Result<ControlPayload<'a>, ParseError>> {
+        let Some(MessageTypeInfo::Control(info)) = self.type_info() else {
+            return None;
+        };
+        let payload = self.payload.as_non_verbose()?;
+        ControlPayload::from_slice(info, payload.message_id(), payload.data(), false)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.source
+    }
+}
+
+impl<'a> Display for DltMessageV2<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Ok(dt) = speedate::DateTime::from_timestamp(
+            self.storage_header.seconds as i64,
+            self.storage_header.microseconds(),
+        ) {
+            write!(
+                f,
+                "{:0>4}/{:0>2}/{:0>2} {} ",
+                dt.date.year, dt.date.month, dt.date.day, dt.time,
+            )?
+        };
+
+        if let Some(timestamp) = self.base_header.timestamp {
+            write!(f, "{timestamp} ")?;
+        }
+
+        write!(f, "{:0>3} ", self.base_header.message_counter)?;
+        write!(f, "{} ", self.ecu_id())?;
+
+        if let (Some(app_id), Some(context_id)) = (self.app_id(), self.context_id()) {
+            write!(f, "{app_id} {context_id} ")?;
+        }
+
+        if let Some(session_id) = self.base_header.session_id {
+            write!(f, "{session_id} ")?;
+        }
+
+        if let Some(type_info) = self.type_info() {
+            // reuse the formatting of version 1 messages, which only differ in their headers
+            DltMessage::fmt_type_info(f, type_info)?;
+        }
+
+        match self.base_header.content_info() {
+            ContentInfo::Verbose => write!(f, "verbose ")?,
+            ContentInfo::NonVerbose | ContentInfo::Control => write!(f, "non-verbose ")?,
+        }
+
+        if let Some(number_of_arguments) = self.base_header.number_of_arguments {
+            write!(f, "{number_of_arguments} ")?;
+        }
+
+        match self.control_payload() {
+            Some(Ok(control)) => write!(f, "{control}"),
+            _ => write!(f, "{}", self.payload),
+        }
+    }
+}
+
+/// A message of either protocol version
+#[derive(Debug)]
+pub enum VersionedMessage<'a> {
+    V1(DltMessage<'a>),
+    V2(DltMessageV2<'a>),
+}
+
+impl<'a> VersionedMessage<'a> {
+    /// Parses a message, dispatching on the protocol version in the header following the storage header
+    pub fn from_slice(buf: &'a [u8]) -> Result<Self, DltError> {
+        let storage_header = StorageHeader::from_slice(buf)?;
+        match buf.get(storage_header.len()).copied().map(protocol_version) {
+            Some(2) => DltMessageV2::from_slice(buf).map(VersionedMessage::V2),
+            _ => DltMessage::from_slice(buf).map(VersionedMessage::V1),
+        }
+    }
+
+    // The length of the message in bytes
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        match self {
+            VersionedMessage::V1(message) => message.len(),
+            VersionedMessage::V2(message) => message.len(),
+        }
+    }
+
+    pub fn payload(&self) -> &Payload<'a> {
+        match self {
+            VersionedMessage::V1(message) => &message.payload,
+            VersionedMessage::V2(message) => &message.payload,
+        }
+    }
+
+    pub fn set_string_decoding(&mut self, string_decoding: StringDecoding) {
+        match self {
+            VersionedMessage::V1(message) => message.payload.set_string_decoding(string_decoding),
+            VersionedMessage::V2(message) => message.payload.set_string_decoding(string_decoding),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            VersionedMessage::V1(message) => message.as_bytes(),
+            VersionedMessage::V2(message) => message.as_bytes(),
+        }
+    }
+}
+
+impl<'a> Display for VersionedMessage<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VersionedMessage::V1(message) => write!(f, "{message}"),
+            VersionedMessage::V2(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn verbose_message() {
+        let bytes = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                      \x4c\x00\x00\x00\x2a\x00\x2b\
+                      \x41\x01\x00\x00\x00\x07\x00\x00\x00\x00\x0d\
+                      \x05ECU1\x00\x04APP\x00\x04CTX\x00\
+                      \x00\x82\x00\x00\x03\x00hi\x00";
+        let message = VersionedMessage::from_slice(bytes).unwrap();
+        assert_eq!(message.len(), bytes.len());
+        let VersionedMessage::V2(message) = message else {
+            panic!("expected a version 2 message");
+        };
+        assert_eq!(message.ecu_id(), "ECU1");
+        assert_eq!(message.app_id(), Some("APP"));
+        assert_eq!(message.context_id(), Some("CTX"));
+        assert!(message.verbose());
+        assert!(message
+            .to_string()
+            .ends_with(" 13.000000007 042 ECU1 APP CTX log info verbose 1 hi "));
+    }
+
+    #[test]
+    fn non_verbose_message() {
+        let bytes = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                      \x41\x00\x00\x00\x00\x00\x16\
+                      \x00\x00\x00\x00\x00\x00\x00\x00\x01\
+                      \x00\x00\x04\xd2\
+                      \xab\xcd";
+        let message = DltMessageV2::from_slice(bytes).unwrap();
+        assert_eq!(message.len(), bytes.len());
+        assert_eq!(message.ecu_id(), "ECU");
+        assert_eq!(message.type_info(), None);
+        assert_eq!(message.payload.to_string(), "[1234] abcd");
+    }
+
+    #[test]
+    fn dispatch_on_version() {
+        let v1 = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\x20\x00\x00\x08\x01\x00\x00\x00";
+        assert!(matches!(
+            VersionedMessage::from_slice(v1),
+            Ok(VersionedMessage::V1(_))
+        ));
+
+        let v2 = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\x42\x00\x00\x00\x00\x00\x0d\x26\x00\x01\x00\x00\x00";
+        assert!(matches!(
+            DltMessage::from_slice(v2),
+            Err(DltError {
+                source: ParseError::Unsupported(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            VersionedMessage::from_slice(v2),
+            Ok(VersionedMessage::V2(_))
+        ));
+    }
+}
@@ -0,0 +1,573 @@
+//! An incremental parser that does no I/O itself.
+//!
+//! Data is pushed into a [`DltParser`] with [`DltParser::feed`] as it arrives, from a socket,
+//! a serial port or anything else, and complete messages are taken out with [`DltParser::poll`].
+//! Fed bytes are copied into the parser once and messages borrow from there.
+//! Messages have to be contiguous, so the bytes of a message that is not complete yet
+//! are moved to the front when the buffer is compacted. This second copy is deliberate,
+//! it only happens once the parsed bytes in front of them outnumber them,
+//! and at most once per message unless the parser resynchronises after an error.
+//! A [`FramedReader`] drives a parser with data from a [`Read`] source.
+
+use std::{
+    io::{self, ErrorKind, Read},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    decoder::DecoderRegistry,
+    error::{DltError, ParseError, ReadError},
+    header::{StandardHeader, StorageHeader},
+    message::DltMessage,
+    payload::StringDecoding,
+    reader::{Decoding, OwnedMessage},
+};
+
+/// How messages are delimited in the fed data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Every message starts with a storage header, like in a DLT file
+    StorageHeader,
+    /// Messages start with the standard header, like on the network.
+    /// They get a synthesized storage header with the time they were fed.
+    Raw,
+    /// Messages start with the `DLS\x01` serial header, followed by the standard header,
+    /// like on the serial output of dlt-daemon. They get a synthesized storage header as well.
+    Serial,
+}
+
+/// The header that precedes messages on serial lines
+const SERIAL_HEADER: &[u8] = b"DLS\x01";
+/// How much [`DltParser::read_from`] reads at once
+const READ_SIZE: usize = 1 << 16;
+
+/// The current time since the unix epoch
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// The result of [`DltParser::poll`]
+#[derive(Debug)]
+pub enum Parsed<'a> {
+    Message(DltMessage<'a>),
+    /// At least `needed` more bytes have to be fed before the next message is complete
+    Incomplete {
+        needed: usize,
+    },
+    /// A malformed message was skipped, polling again continues with the next one
+    Error(DltError),
+}
+
+/// The variants of [`Parsed`] that don't borrow from the parser
+pub(crate) enum NoMessage {
+    Incomplete { needed: usize },
+    Error(DltError),
+}
+
+/// A push parser, see the [module level documentation](self)
+#[derive(Debug)]
+pub struct DltParser {
+    framing: Framing,
+    // the space after `end` is kept for the next reads, so it is only zero-filled once
+    buf: Vec<u8>,
+    // everything before `start` was already parsed
+    start: usize,
+    end: usize,
+    // skipping data after a malformed message until the next message starts
+    resyncing: bool,
+    // the time at which the last data was fed, for synthesized storage headers
+    received: Duration,
+    ecu_id: String,
+    decoding: Decoding,
+}
+
+impl DltParser {
+    pub fn new(framing: Framing) -> Self {
+        Self {
+            framing,
+            buf: Vec::new(),
+            start: 0,
+            end: 0,
+            resyncing: false,
+            received: Duration::ZERO,
+            ecu_id: String::new(),
+            decoding: Decoding::default(),
+        }
+    }
+
+    /// Sets the ECU id of synthesized storage headers, for messages without one in their standard header
+    pub fn with_ecu_id(mut self, ecu_id: &str) -> Self {
+        self.ecu_id = ecu_id.to_string();
+        self
+    }
+
+    /// Sets how string arguments that are not valid UTF-8 are handled in all messages
+    pub fn with_string_decoding(mut self, string_decoding: StringDecoding) -> Self {
+        self.decoding.string_decoding = string_decoding;
+        self
+    }
+
+    /// Sets the decoders that format the payloads of all messages, see [`DltMessage::set_decoder`]
+    pub fn with_decoders(mut self, decoders: Arc<DecoderRegistry>) -> Self {
+        self.decoding.decoders = Some(decoders);
+        self
+    }
+
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// The number of fed bytes that were not parsed yet
+    pub fn buffered(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Adds data that was received just now
+    pub fn feed(&mut self, data: &[u8]) {
+        self.feed_at(data, now());
+    }
+
+    /// Adds data that was received at `timestamp`, since the unix epoch,
+    /// which is used for the synthesized storage headers of the messages completed by it
+    pub fn feed_at(&mut self, data: &[u8], timestamp: Duration) {
+        self.compact();
+        let end = self.end + data.len();
+        if end > self.buf.len() {
+            self.buf.resize(end, 0);
+        }
+        self.buf[self.end..end].copy_from_slice(data);
+        self.end = end;
+        self.received = timestamp;
+    }
+
+    /// Reads data from `reader` directly into the parser, instead of copying it in with [`DltParser::feed`].
+    /// Returns the number of bytes read, which is 0 at the end of the input.
+    pub fn read_from(&mut self, reader: &mut impl Read) -> io::Result<usize> {
+        self.compact();
+        let end = self.end + READ_SIZE;
+        if end > self.buf.len() {
+            self.buf.resize(end, 0);
+        }
+        let read = reader.read(&mut self.buf[self.end..end]);
+        self.end += *read.as_ref().unwrap_or(&0);
+        self.received = now();
+        read
+    }
+
+    fn compact(&mut self) {
+        // only move the unparsed data once it is smaller than what was already parsed
+        if self.start > 0 && self.start >= self.buffered() {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+    }
+
+    /// Takes the next complete message out of the parser
+    pub fn poll(&mut self) -> Parsed<'_> {
+        let length = match self.next_length() {
+            Ok(length) => length,
+            Err(NoMessage::Incomplete { needed }) => return Parsed::Incomplete { needed },
+            Err(NoMessage::Error(err)) => return Parsed::Error(err),
+        };
+        match self.take(length) {
+            Ok(message) => Parsed::Message(message),
+            Err(err) => Parsed::Error(err),
+        }
+    }
+
+    /// Discards all buffered data, e.g. after a connection was lost in the middle of a message
+    pub fn reset(&mut self) {
+        self.start = 0;
+        self.end = 0;
+        self.resyncing = false;
+    }
+
+    pub(crate) fn decoding(&self) -> &Decoding {
+        &self.decoding
+    }
+
+    /// Takes the message of `length` bytes returned by [`DltParser::next_length`]
+    pub(crate) fn take(&mut self, length: usize) -> Result<DltMessage<'_>, DltError> {
+        let start = self.start;
+        self.start += length;
+        let mut message = self.message(&self.buf[start..start + length])?;
+        self.decoding.apply(&mut message);
+        Ok(message)
+    }
+
+    fn message<'a>(&'a self, data: &'a [u8]) -> Result<DltMessage<'a>, DltError> {
+        match self.framing {
+            Framing::StorageHeader => DltMessage::from_slice(data),
+            Framing::Raw => DltMessage::from_raw_slice(data, self.received, &self.ecu_id),
+            Framing::Serial => {
+                DltMessage::from_raw_slice(strip_serial_header(data)?, self.received, &self.ecu_id)
+                    .map_err(after_serial_header)
+            }
+        }
+    }
+
+    /// The length of the message at the start of `data`, of which only the headers are parsed
+    fn length(&self, data: &[u8]) -> Result<usize, DltError> {
+        match self.framing {
+            Framing::StorageHeader => DltMessage::length(data),
+            Framing::Raw => DltMessage::raw_length(data),
+            Framing::Serial => DltMessage::raw_length(strip_serial_header(data)?)
+                .map(|length| SERIAL_HEADER.len() + length)
+                .map_err(after_serial_header),
+        }
+    }
+
+    /// The length of the next complete message at `start`, or what to return instead
+    pub(crate) fn next_length(&mut self) -> Result<usize, NoMessage> {
+        loop {
+            if self.resyncing {
+                match self.framing {
+                    Framing::Raw => self.find_plausible()?,
+                    Framing::StorageHeader | Framing::Serial => self.find_pattern()?,
+                }
+            }
+            match self.length(&self.buf[self.start..self.end]) {
+                Ok(length) => {
+                    self.resyncing = false;
+                    return Ok(length);
+                }
+                Err(DltError {
+                    advance_by: None,
+                    source: ParseError::NotEnoughData { needed, available },
+                }) => {
+                    return Err(NoMessage::Incomplete {
+                        needed: needed.saturating_sub(available).max(1),
+                    })
+                }
+                Err(err) => match err.advance_by {
+                    Some(advance_by) => {
+                        self.resyncing = false;
+                        self.start += advance_by.min(self.buffered());
+                        return Err(NoMessage::Error(err));
+                    }
+                    // without a pattern every byte could be the start of the next message,
+                    // the error is only reported for the first one
+                    None => {
+                        self.start += 1;
+                        if !self.resyncing {
+                            self.resyncing = true;
+                            return Err(NoMessage::Error(err));
+                        }
+                    }
+                },
+            }
+        }
+    }
+
+    /// Skips to the next `DLT\x01` or `DLT\x02` pattern, like `DltFile` does after an error,
+    /// or to the next `DLS\x01` pattern with serial framing
+    fn find_pattern(&mut self) -> Result<(), NoMessage> {
+        let (pattern, versions): (_, &[u8]) = match self.framing {
+            Framing::Serial => (&SERIAL_HEADER[..3], &[0x1]),
+            Framing::StorageHeader | Framing::Raw => (StorageHeader::PATTERN, &[0x1, 0x2]),
+        };
+        let available = &self.buf[self.start..self.end];
+        let version = pattern.len();
+        let found = memchr::memmem::find_iter(available, pattern).find(|&index| {
+            available
+                .get(index + version)
+                .is_none_or(|version| versions.contains(version))
+        });
+        match found {
+            Some(index) => {
+                self.start += index;
+                if index + version < available.len() {
+                    self.resyncing = false;
+                    return Ok(());
+                }
+            }
+            // keep what could be the beginning of the pattern
+            None => self.start += available.len().saturating_sub(version - 1),
+        }
+        Err(NoMessage::Incomplete { needed: 1 })
+    }
+
+    /// Skips to the next message without storage header that looks valid, like `DltStreamFile` does.
+    /// A candidate is only judged once it and the standard header after it are buffered,
+    /// so a corrupted length delays the resynchronisation by at most 64 KiB instead of losing
+    /// the messages that arrive in the meantime.
+    fn find_plausible(&mut self) -> Result<(), NoMessage> {
+        loop {
+            let available = &self.buf[self.start..self.end];
+            let needed = match DltMessage::raw_length(available) {
+                Ok(length) => length + StandardHeader::MIN_LENGTH,
+                Err(DltError {
+                    advance_by: None,
+                    source: ParseError::NotEnoughData { needed, available },
+                }) => {
+                    return Err(NoMessage::Incomplete {
+                        needed: needed.saturating_sub(available).max(1),
+                    })
+                }
+                Err(_) => {
+                    self.start += 1;
+                    continue;
+                }
+            };
+            if needed > available.len() {
+                return Err(NoMessage::Incomplete {
+                    needed: needed - available.len(),
+                });
+            }
+            if DltMessage::plausible_raw_length(available).is_some() {
+                self.resyncing = false;
+                return Ok(());
+            }
+            self.start += 1;
+        }
+    }
+}
+
+/// The data after the serial header at the start of `data`
+fn strip_serial_header(data: &[u8]) -> Result<&[u8], DltError> {
+    if data.len() < SERIAL_HEADER.len() {
+        return Err(ParseError::NotEnoughData {
+            needed: SERIAL_HEADER.len(),
+            available: data.len(),
+        }
+        .into());
+    }
+    if !data.starts_with(SERIAL_HEADER) {
+        return Err(ParseError::MissingDltPattern.into());
+    }
+    Ok(&data[SERIAL_HEADER.len()..])
+}
+
+/// Makes an error of the data after a serial header skip the serial header as well
+fn after_serial_header(mut err: DltError) -> DltError {
+    err.advance_by = err
+        .advance_by
+        .map(|advance_by| advance_by + SERIAL_HEADER.len());
+    err
+}
+
+/// Reads messages with any [`Framing`] from a [`Read`] source,
+/// e.g. from a tty or a captured UART dump with [`Framing::Serial`]
+#[derive(Debug)]
+pub struct FramedReader<R> {
+    reader: R,
+    parser: DltParser,
+    eof: bool,
+}
+
+impl<R: Read> FramedReader<R> {
+    /// Reads messages with the framing and settings of `parser`
+    pub fn new(reader: R, parser: DltParser) -> Self {
+        Self {
+            reader,
+            parser,
+            eof: false,
+        }
+    }
+
+    pub fn parser(&self) -> &DltParser {
+        &self.parser
+    }
+
+    /// Sets the decoders that format the payloads of all messages, see [`DltParser::with_decoders`]
+    pub fn with_decoders(mut self, decoders: Arc<DecoderRegistry>) -> Self {
+        self.parser = self.parser.with_decoders(decoders);
+        self
+    }
+
+    /// Returns the underlying reader, any buffered data is lost
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next message, which borrows the reader until the next call.
+    /// Malformed messages are yielded as errors and skipped, an incomplete message at the end is discarded.
+    pub fn next_message(&mut self) -> Option<Result<DltMessage<'_>, ReadError>> {
+        let length = loop {
+            match self.parser.next_length() {
+                Ok(length) => break length,
+                Err(NoMessage::Error(err)) => return Some(Err(err.into())),
+                Err(NoMessage::Incomplete { .. }) if self.eof => return None,
+                Err(NoMessage::Incomplete { .. }) => {
+                    match self.parser.read_from(&mut self.reader) {
+                        Ok(read) => self.eof = read == 0,
+                        Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                        Err(err) => return Some(Err(err.into())),
+                    }
+                }
+            }
+        };
+        Some(self.parser.take(length).map_err(ReadError::from))
+    }
+}
+
+/// Yields owned copies of the messages, use [`FramedReader::next_message`] to avoid the copies
+impl<R: Read> Iterator for FramedReader<R> {
+    type Item = Result<OwnedMessage, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let decoding = self.parser.decoding.clone();
+        Some(
+            self.next_message()?
+                .map(|message| OwnedMessage::new(&message, decoding)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const MESSAGE: &[u8] = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                             \x21\x00\x00\x17\
+                             \x41\x01APP1CTX1\
+                             \x00\x82\x00\x00\x03\x00hi\x00";
+
+    /// Feeds `data` in chunks of `chunk` bytes and collects the length of each message,
+    /// or `None` for each error
+    fn feed_chunked(parser: &mut DltParser, data: &[u8], chunk: usize) -> Vec<Option<usize>> {
+        let mut lengths = Vec::new();
+        for data in data.chunks(chunk) {
+            parser.feed_at(data, Duration::from_secs(42));
+            loop {
+                match parser.poll() {
+                    Parsed::Message(message) => lengths.push(Some(message.len())),
+                    Parsed::Error(_) => lengths.push(None),
+                    Parsed::Incomplete { needed } => {
+                        assert!(needed > 0);
+                        break;
+                    }
+                }
+            }
+        }
+        lengths
+    }
+
+    #[test]
+    fn storage_header_framing() {
+        let data = [MESSAGE, MESSAGE, MESSAGE].concat();
+        for chunk in 1..data.len() {
+            let mut parser = DltParser::new(Framing::StorageHeader);
+            assert_eq!(
+                feed_chunked(&mut parser, &data, chunk),
+                vec![Some(MESSAGE.len()); 3]
+            );
+            assert_eq!(parser.buffered(), 0);
+        }
+
+        let mut parser = DltParser::new(Framing::StorageHeader);
+        parser.feed(&MESSAGE[..20]);
+        assert!(
+            matches!(parser.poll(), Parsed::Incomplete { needed } if needed == MESSAGE.len() - 20)
+        );
+    }
+
+    #[test]
+    fn raw_framing() {
+        let raw = &MESSAGE[16..];
+        let data = [raw, raw].concat();
+        for chunk in 1..data.len() {
+            let mut parser = DltParser::new(Framing::Raw);
+            assert_eq!(
+                feed_chunked(&mut parser, &data, chunk),
+                vec![Some(raw.len()); 2]
+            );
+        }
+
+        let mut parser = DltParser::new(Framing::Raw).with_ecu_id("ECU2");
+        parser.feed_at(raw, Duration::new(42, 1000));
+        let Parsed::Message(message) = parser.poll() else {
+            panic!("expected a message");
+        };
+        assert!(message.storage_header.is_synthesized());
+        assert_eq!(message.ecu_id(), "ECU2");
+        assert_eq!(message.storage_header.timestamp(), Duration::new(42, 1000));
+        assert_eq!(message.as_bytes(), raw);
+        assert_eq!(message.payload.to_string(), "hi ");
+    }
+
+    #[test]
+    fn resynchronisation() {
+        let mut corrupted = MESSAGE.to_vec();
+        // a standard header length smaller than the header itself
+        corrupted[19] = 0x02;
+        let data = [&b"garbage DL"[..], MESSAGE, &corrupted, b"DLT\x03", MESSAGE].concat();
+        for chunk in 1..data.len() {
+            let mut parser = DltParser::new(Framing::StorageHeader);
+            assert_eq!(
+                feed_chunked(&mut parser, &data, chunk),
+                [None, Some(MESSAGE.len()), None, Some(MESSAGE.len())]
+            );
+        }
+
+        // an invalid length, after which the data is skipped byte by byte until a header of version 1
+        let raw = &MESSAGE[16..];
+        let with_ecu_id = b"\x25\x00\x00\x1bECU1\x41\x01APP1CTX1\x00\x82\x00\x00\x03\x00hi\x00";
+        let data = [&b"\x20\x00\x00\x02"[..], with_ecu_id, raw, with_ecu_id].concat();
+        for chunk in 1..data.len() {
+            let mut parser = DltParser::new(Framing::Raw);
+            assert_eq!(
+                feed_chunked(&mut parser, &data, chunk),
+                [
+                    None,
+                    Some(with_ecu_id.len()),
+                    Some(raw.len()),
+                    Some(with_ecu_id.len())
+                ]
+            );
+        }
+
+        // a header of version 1 in the garbage, whose length reaches into the third message,
+        // is only skipped once it is complete
+        let data = [&b"\x20\x00\x00\x02\x20\x00\x00\x40"[..], raw, raw, raw, raw].concat();
+        for chunk in 1..data.len() {
+            let mut parser = DltParser::new(Framing::Raw);
+            assert_eq!(
+                feed_chunked(&mut parser, &data, chunk),
+                [
+                    None,
+                    Some(raw.len()),
+                    Some(raw.len()),
+                    Some(raw.len()),
+                    Some(raw.len())
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn serial_framing() {
+        let serial = [b"DLS\x01", &MESSAGE[16..]].concat();
+        // line noise, a corrupted serial header and a truncated message at the end
+        let data = [
+            &b"noise DL"[..],
+            &serial,
+            b"DLS\x02",
+            &serial,
+            &serial[..10],
+        ]
+        .concat();
+        for chunk in 1..data.len() {
+            let mut parser = DltParser::new(Framing::Serial);
+            assert_eq!(
+                feed_chunked(&mut parser, &data, chunk),
+                [None, Some(serial.len() - 4), None, Some(serial.len() - 4)]
+            );
+        }
+
+        let parser = DltParser::new(Framing::Serial).with_ecu_id("UART");
+        let mut reader = FramedReader::new(&data[..], parser);
+        assert!(reader.next_message().unwrap().is_err());
+        let message = reader.next_message().unwrap().unwrap();
+        assert_eq!(message.ecu_id(), "UART");
+        assert!(message.storage_header.is_synthesized());
+        let messages = reader.flatten().collect::<Vec<_>>();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].as_bytes(), &MESSAGE[16..]);
+    }
+}
@@ -1,17 +1,22 @@
 #![allow(unused)]
-use std::{fmt::Display, marker::PhantomData};
+use std::{borrow::Cow, fmt::Display, marker::PhantomData};
 
 use bytes::Buf;
 use simdutf8::basic::{from_utf8, Utf8Error};
 
 use crate::{
+    ensure_remaining,
     error::{DltError, ParseError},
-    get_slice, get_str,
+    get_slice, get_str, msb,
 };
+
+pub mod control;
+
 #[derive(Debug)]
 pub struct NonVerbosePayload<'a> {
     message_id: u32,
     data: &'a [u8],
+    string_decoding: StringDecoding,
 }
 
 impl<'a> NonVerbosePayload<'a> {
@@ -26,23 +31,61 @@ impl<'a> NonVerbosePayload<'a> {
                 available: buf.remaining(),
             });
         }
+        // every non-verbose payload starts with a message id
+        if length < 4 {
+            return Err(ParseError::NotEnoughData {
+                needed: 4,
+                available: length,
+            });
+        }
 
         let message_id = if msb_first {
             buf.get_u32()
         } else {
             buf.get_u32_le()
         };
-        let data = buf.get(..length).ok_or_else(|| ParseError::NotEnoughData {
-            needed: length,
-            available: buf.remaining(),
-        })?;
-        Ok(Self { message_id, data })
+        let data = get_slice!(buf, length - 4);
+        Ok(Self {
+            message_id,
+            data,
+            string_decoding: StringDecoding::default(),
+        })
+    }
+
+    /// For protocol versions that carry the message id in the header instead of the payload
+    #[cfg(feature = "r22-11")]
+    pub(crate) fn with_message_id(message_id: u32, data: &'a [u8]) -> Self {
+        Self {
+            message_id,
+            data,
+            string_decoding: StringDecoding::default(),
+        }
+    }
+
+    pub fn message_id(&self) -> u32 {
+        self.message_id
+    }
+
+    /// The payload following the message id
+    pub fn data(&self) -> &'a [u8] {
+        self.data
     }
 
     pub fn as_str(&self) -> Result<&str, Utf8Error> {
         Ok(from_utf8(self.data)?.trim_end_matches('\0'))
     }
 
+    /// Sets how string arguments that are not valid UTF-8 are handled when this payload is decoded,
+    /// see [`Catalog::decode`](crate::catalog::Catalog::decode)
+    pub fn set_string_decoding(&mut self, string_decoding: StringDecoding) {
+        self.string_decoding = string_decoding;
+    }
+
+    pub fn string_decoding(&self) -> StringDecoding {
+        self.string_decoding
+    }
+
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         4 + self.data.len()
     }
@@ -62,6 +105,7 @@ impl<'a> Display for NonVerbosePayload<'a> {
 pub struct VerbosePayload<'a> {
     data: &'a [u8],
     msb_first: bool,
+    string_decoding: StringDecoding,
 }
 
 impl<'a> VerbosePayload<'a> {
@@ -70,21 +114,33 @@ impl<'a> VerbosePayload<'a> {
             needed: length,
             available: buf.remaining(),
         })?;
-        Ok(Self { data, msb_first })
+        Ok(Self {
+            data,
+            msb_first,
+            string_decoding: StringDecoding::default(),
+        })
     }
 
     pub fn new(buf: &'a [u8], msb_first: bool) -> Self {
         Self {
             data: buf,
             msb_first,
+            string_decoding: StringDecoding::default(),
         }
     }
 
+    /// Sets how string arguments that are not valid UTF-8 are handled
+    pub fn set_string_decoding(&mut self, string_decoding: StringDecoding) {
+        self.string_decoding = string_decoding;
+    }
+
     pub fn arguments(&self) -> Arguments<'a> {
         Arguments {
             data: self.data,
             index: 0,
             msb_first: self.msb_first,
+            string_decoding: self.string_decoding,
+            depth: 0,
             fatal: false,
         }
     }
@@ -92,6 +148,10 @@ impl<'a> VerbosePayload<'a> {
     pub fn len(&self) -> usize {
         self.data.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
 }
 
 impl<'a> Display for VerbosePayload<'a> {
@@ -132,6 +192,18 @@ impl<'a> Payload<'a> {
             Payload::Verbose(v) => v.len(),
         }
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sets how string arguments that are not valid UTF-8 are handled
+    pub fn set_string_decoding(&mut self, string_decoding: StringDecoding) {
+        match self {
+            Payload::NonVerbose(nv) => nv.set_string_decoding(string_decoding),
+            Payload::Verbose(v) => v.set_string_decoding(string_decoding),
+        }
+    }
 }
 
 impl<'a> Display for Payload<'a> {
@@ -157,7 +229,7 @@ enum TypeInfoMask {
 #[rustfmt::skip]
 #[derive(Debug)]
 #[repr(u32)]
-enum TypeInfo {
+pub(crate) enum TypeInfo {
     Size8 =        0b00000000000000000000000000000001,
     Size16 =       0b00000000000000000000000000000010,
     Size32 =       0b00000000000000000000000000000011,
@@ -186,6 +258,9 @@ pub struct Arguments<'a> {
     data: &'a [u8],
     index: usize,
     msb_first: bool,
+    string_decoding: StringDecoding,
+    /// how deeply nested in structs these arguments are
+    depth: usize,
     fatal: bool,
 }
 
@@ -196,7 +271,12 @@ impl<'a> Iterator for Arguments<'a> {
         if self.fatal || self.index >= self.data.len() {
             return None;
         }
-        match Argument::new(&self.data[self.index..], self.msb_first) {
+        match Argument::nested(
+            &self.data[self.index..],
+            self.msb_first,
+            self.string_decoding,
+            self.depth,
+        ) {
             Ok(arg) => {
                 self.index += arg.len();
                 Some(Ok(arg))
@@ -212,104 +292,316 @@ impl<'a> Iterator for Arguments<'a> {
 #[derive(Debug, Clone)]
 pub struct Argument<'a> {
     type_info: u32,
-    name: Option<&'a str>,
-    unit: Option<&'a str>,
+    name: Option<Cow<'a, str>>,
+    unit: Option<Cow<'a, str>>,
+    fixed_point: Option<FixedPoint>,
+    trace_info: Option<Cow<'a, str>>,
+    string_coding: Option<StringCoding>,
     value: Value<'a>,
+    length: usize,
 }
 
 impl<'a> Argument<'a> {
-    const MIN_LENGTH: usize = todo!();
+    const MIN_LENGTH: usize = 4 /*type info*/;
 
-    fn new(mut buf: &'a [u8], msb_first: bool) -> Result<Argument<'_>, ParseError> {
-        macro_rules! msb {
-            ($be: expr, $le: expr) => {{
-                if msb_first {
-                    $be
-                } else {
-                    $le
-                }
-            }};
-        }
+    /// Structs and arrays can be nested arbitrarily deep, this limit prevents untrusted input
+    /// from overflowing the stack
+    const MAX_DEPTH: usize = 64;
+
+    fn new(
+        buf: &'a [u8],
+        msb_first: bool,
+        string_decoding: StringDecoding,
+    ) -> Result<Argument<'a>, ParseError> {
+        Self::nested(buf, msb_first, string_decoding, 0)
+    }
+
+    fn nested(
+        mut buf: &'a [u8],
+        msb_first: bool,
+        string_decoding: StringDecoding,
+        depth: usize,
+    ) -> Result<Argument<'a>, ParseError> {
+        ensure_remaining!(buf, Self::MIN_LENGTH);
+        let type_info = if msb_first {
+            buf.get_u32()
+        } else {
+            buf.get_u32_le()
+        };
+        let mut argument = Self::with_type_info(type_info, buf, msb_first, string_decoding, depth)?;
+        argument.length += Self::MIN_LENGTH;
+        Ok(argument)
+    }
+
+    /// Parses the data of an argument whose type info is already known,
+    /// like in non-verbose payloads where it is described in a separate catalog
+    pub(crate) fn with_type_info(
+        type_info: u32,
+        buf: &'a [u8],
+        msb_first: bool,
+        string_decoding: StringDecoding,
+        depth: usize,
+    ) -> Result<Argument<'a>, ParseError> {
+        let source = buf;
+        let mut buf = buf;
 
-        let type_info = buf.get_u32_le();
         let var_info = (type_info & TypeInfo::VariableInfo as u32) != 0;
         let fixed_point = (type_info & TypeInfo::FixedPoint as u32) != 0;
+        let array = (type_info & TypeInfo::Array as u32) != 0;
+        let trace_info = (type_info & TypeInfo::TraceInfo as u32) != 0;
 
         let type_length = type_info & TypeInfoMask::Length as u32;
-        let r#type = type_info & TypeInfoMask::Type as u32;
+        let r#type = type_info
+            & TypeInfoMask::Type as u32
+            & !(TypeInfo::Array as u32 | TypeInfo::TraceInfo as u32);
 
         let arg_type = match ArgType::try_from(r#type) {
             Ok(arg_type) => arg_type,
             Err(unknown) => return Err(ParseError::UnknownArgumentType(unknown)),
         };
 
-        if var_info {
-            return Err(ParseError::Unsupported("var info"));
-        }
-        if fixed_point {
+        // only integers can be fixed point values
+        if fixed_point && !matches!(arg_type, ArgType::Signed | ArgType::Unsigned) {
             return Err(ParseError::Unsupported("fixed point"));
         }
 
-        let value = match arg_type {
-            ArgType::Bool => Value::Bool(buf.get_u8() != 0),
-            ArgType::Signed => match type_length {
-                0x01 => Value::I8(buf.get_i8()),
-                0x02 => Value::I16(msb!(buf.get_i16(), buf.get_i16_le())),
-                0x03 => Value::I32(msb!(buf.get_i32(), buf.get_i32_le())),
-                0x04 => Value::I64(msb!(buf.get_i64(), buf.get_i64_le())),
-                0x05 => Value::I128(msb!(buf.get_i128(), buf.get_i128_le())),
-                _ => unreachable!(),
-            },
-            ArgType::Unsigned => match type_length {
-                0x01 => Value::U8(buf.get_u8()),
-                0x02 => Value::U16(msb!(buf.get_u16(), buf.get_u16_le())),
-                0x03 => Value::U32(msb!(buf.get_u32(), buf.get_u32_le())),
-                0x04 => Value::U64(msb!(buf.get_u64(), buf.get_u64_le())),
-                0x05 => Value::U128(msb!(buf.get_u128(), buf.get_u128_le())),
-                _ => unreachable!(),
-            },
-            ArgType::Float => match type_length {
-                0x01 => unreachable!(),
-                0x02 => return Err(ParseError::Unsupported("f16")),
-                0x03 => Value::F32(msb!(buf.get_f32(), buf.get_f32_le())),
-                0x04 => Value::F64(msb!(buf.get_f64(), buf.get_f64_le())),
-                0x05 => return Err(ParseError::Unsupported("f128")),
-                _ => unreachable!(),
-            },
+        // the trace info precedes all other data of the argument
+        let trace_info = if trace_info {
+            ensure_remaining!(buf, 2);
+            let length = msb!(msb_first, buf.get_u16(), buf.get_u16_le());
+            let bytes = get_slice!(buf, length as usize);
+            Some(StringCoding::Utf8.decode(bytes, string_decoding)?)
+        } else {
+            None
+        };
+
+        let mut name = None;
+        let mut unit = None;
+        let mut fixed_point_info = None;
+        let mut string_coding = None;
+        // the array bit is not part of `r#type`, it marks an array of elements of `arg_type`
+        let kind = if array { ArgType::Array } else { arg_type };
+        let value = match kind {
             ArgType::Array => {
-                return Err(ParseError::UnimplementedArgumentType("array"));
+                let array = Array::from_buf(
+                    &mut buf,
+                    arg_type,
+                    type_length,
+                    var_info,
+                    fixed_point,
+                    msb_first,
+                    string_decoding,
+                )?;
+                name = array.name.clone();
+                unit = array.unit.clone();
+                fixed_point_info = array.fixed_point;
+                Value::Array(array)
+            }
+            ArgType::Bool | ArgType::Signed | ArgType::Unsigned | ArgType::Float => {
+                if var_info {
+                    // booleans have a name but no unit
+                    let with_unit = !matches!(arg_type, ArgType::Bool);
+                    (name, unit) = read_var_info(&mut buf, with_unit, msb_first, string_decoding)?;
+                }
+                if fixed_point {
+                    fixed_point_info =
+                        Some(FixedPoint::from_buf(&mut buf, type_length, msb_first)?);
+                }
+                Value::scalar(&mut buf, arg_type, type_length, msb_first)?
             }
             ArgType::String => {
-                let length = msb!(buf.get_u16(), buf.get_u16_le());
-                Value::String(get_str!(buf, length as usize)?.trim_end_matches('\0'))
+                ensure_remaining!(buf, 2);
+                let length = msb!(msb_first, buf.get_u16(), buf.get_u16_le());
+                if var_info {
+                    (name, _) = read_var_info(&mut buf, false, msb_first, string_decoding)?;
+                }
+                let coding = StringCoding::try_from(type_info)?;
+                string_coding = Some(coding);
+                let bytes = get_slice!(buf, length as usize);
+                Value::String(coding.decode(bytes, string_decoding)?)
             }
             ArgType::Raw => {
-                let length = msb!(buf.get_u16(), buf.get_u16_le());
+                ensure_remaining!(buf, 2);
+                let length = msb!(msb_first, buf.get_u16(), buf.get_u16_le());
+                if var_info {
+                    (name, _) = read_var_info(&mut buf, false, msb_first, string_decoding)?;
+                }
                 Value::Raw(get_slice!(buf, length as usize))
             }
             ArgType::Struct => {
-                return Err(ParseError::UnimplementedArgumentType("struct"));
+                let r#struct =
+                    Struct::from_buf(&mut buf, var_info, msb_first, string_decoding, depth)?;
+                name = r#struct.name.clone();
+                Value::Struct(r#struct)
             }
         };
         Ok(Argument {
             type_info,
-            name: None,
-            unit: None,
+            name,
+            unit,
+            fixed_point: fixed_point_info,
+            trace_info,
+            string_coding,
             value,
+            length: source.len() - buf.remaining(),
         })
     }
 
-    fn len(&self) -> usize {
-        4 + self.value.len()
+    /// A string argument that is not part of the payload, like the static text of a non-verbose message
+    pub(crate) fn text(text: &'a str) -> Argument<'a> {
+        Argument {
+            type_info: TypeInfo::String as u32 | TypeInfo::Utf8 as u32,
+            name: None,
+            unit: None,
+            fixed_point: None,
+            trace_info: None,
+            string_coding: Some(StringCoding::Utf8),
+            value: Value::String(Cow::Borrowed(text)),
+            length: 0,
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn unit(&self) -> Option<&str> {
+        self.unit.as_deref()
+    }
+
+    pub fn value(&self) -> &Value<'a> {
+        &self.value
+    }
+
+    /// The quantization and offset of a fixed point value
+    pub fn fixed_point(&self) -> Option<FixedPoint> {
+        self.fixed_point
+    }
+
+    /// The trace info (TRAI) of this argument, e.g. a module or function identifier
+    pub fn trace_info(&self) -> Option<&str> {
+        self.trace_info.as_deref()
+    }
+
+    /// The declared coding of a string argument
+    pub fn string_coding(&self) -> Option<StringCoding> {
+        self.string_coding
+    }
+
+    /// The physical value of a fixed point argument, i.e. `raw * quantization + offset`.
+    /// Returns `None` if this is not a (scalar) fixed point argument.
+    pub fn physical_value(&self) -> Option<f64> {
+        self.fixed_point?.physical_value(&self.value)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.length
     }
 }
 
 impl<'a> Display for Argument<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.value)
+        if let Some(trace_info) = &self.trace_info {
+            write!(f, "[{trace_info}] ")?;
+        }
+        if let Some(name) = self.name().filter(|name| !name.is_empty()) {
+            write!(f, "{name}=")?;
+        }
+        write!(f, "{}", self.value)?;
+        if let Some(unit) = self.unit().filter(|unit| !unit.is_empty()) {
+            write!(f, " {unit}")?;
+        }
+        Ok(())
     }
 }
 
+/// The quantization and offset (FIXP) of a fixed point value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedPoint {
+    pub quantization: f32,
+    pub offset: i128,
+}
+
+impl FixedPoint {
+    fn from_buf(buf: &mut &[u8], type_length: u32, msb_first: bool) -> Result<Self, ParseError> {
+        // the offset is 32 bit for values of up to 32 bit, otherwise it has the size of the value
+        let offset_size = match type_length {
+            0x01..=0x03 => 4,
+            0x04 => 8,
+            0x05 => 16,
+            _ => return Err(ParseError::Unsupported("type length")),
+        };
+        let needed = 4 + offset_size;
+        if buf.remaining() < needed {
+            return Err(ParseError::NotEnoughData {
+                needed,
+                available: buf.remaining(),
+            });
+        }
+
+        let quantization = msb!(msb_first, buf.get_f32(), buf.get_f32_le());
+        let offset = match offset_size {
+            4 => msb!(msb_first, buf.get_i32(), buf.get_i32_le()) as i128,
+            8 => msb!(msb_first, buf.get_i64(), buf.get_i64_le()) as i128,
+            _ => msb!(msb_first, buf.get_i128(), buf.get_i128_le()),
+        };
+        Ok(Self {
+            quantization,
+            offset,
+        })
+    }
+
+    /// Applies `raw * quantization + offset` to an integer value
+    pub fn physical_value(&self, raw: &Value<'_>) -> Option<f64> {
+        let raw = match *raw {
+            Value::U8(u) => u as f64,
+            Value::U16(u) => u as f64,
+            Value::U32(u) => u as f64,
+            Value::U64(u) => u as f64,
+            Value::U128(u) => u as f64,
+            Value::I8(i) => i as f64,
+            Value::I16(i) => i as f64,
+            Value::I32(i) => i as f64,
+            Value::I64(i) => i as f64,
+            Value::I128(i) => i as f64,
+            _ => return None,
+        };
+        Some(raw * self.quantization as f64 + self.offset as f64)
+    }
+}
+
+/// The name and unit of an argument
+type VarInfo<'a> = (Option<Cow<'a, str>>, Option<Cow<'a, str>>);
+
+/// Reads the variable info (VARI) that precedes the data of an argument.
+/// Only numeric arguments carry a unit, so it is only read if `with_unit` is set.
+fn read_var_info<'a>(
+    buf: &mut &'a [u8],
+    with_unit: bool,
+    msb_first: bool,
+    string_decoding: StringDecoding,
+) -> Result<VarInfo<'a>, ParseError> {
+    let lengths_size = 2 + 2 * with_unit as usize;
+    ensure_remaining!(buf, lengths_size);
+    let mut read_length = || {
+        if msb_first {
+            buf.get_u16() as usize
+        } else {
+            buf.get_u16_le() as usize
+        }
+    };
+    let name_length = read_length();
+    let unit_length = if with_unit { read_length() } else { 0 };
+
+    let name = StringCoding::Utf8.decode(get_slice!(buf, name_length), string_decoding)?;
+    let unit = if with_unit {
+        Some(StringCoding::Utf8.decode(get_slice!(buf, unit_length), string_decoding)?)
+    } else {
+        None
+    };
+    Ok((Some(name), unit))
+}
+
 #[derive(Debug, Clone)]
 pub enum Value<'a> {
     Bool(bool),
@@ -323,25 +615,75 @@ pub enum Value<'a> {
     I32(i32),
     I64(i64),
     I128(i128),
+    F16(F16),
     F32(f32),
     F64(f64),
-    //Array, // unsupported
-    String(&'a str),
+    F128(F128),
+    Array(Array<'a>),
+    String(Cow<'a, str>),
     Raw(&'a [u8]),
+    Struct(Struct<'a>),
 }
 
 impl<'a> Value<'a> {
-    fn len(&self) -> usize {
-        match self {
-            Value::U8(_) | Value::I8(_) | Value::Bool(_) => 1,
-            Value::U16(_) | Value::I16(_) => 2,
-            Value::U32(_) | Value::I32(_) | Value::F32(_) => 4,
-            Value::U64(_) | Value::I64(_) | Value::F64(_) => 8,
-            Value::U128(_) | Value::I128(_) => 16,
-            Value::String(s) => s.len() + 1 + 2, /*length of string is u16*/
-            Value::Raw(r) => r.len() + 2,        /*length of raw is u16*/
+    /// The size in bytes of a single `Bool`, `Signed`, `Unsigned` or `Float` value
+    fn scalar_size(arg_type: ArgType, type_length: u32) -> Result<usize, ParseError> {
+        match (arg_type, type_length) {
+            (ArgType::Bool, _) => Ok(1),
+            (ArgType::Signed | ArgType::Unsigned, 0x01) => Ok(1),
+            (ArgType::Signed | ArgType::Unsigned | ArgType::Float, 0x02) => Ok(2),
+            (ArgType::Signed | ArgType::Unsigned | ArgType::Float, 0x03) => Ok(4),
+            (ArgType::Signed | ArgType::Unsigned | ArgType::Float, 0x04) => Ok(8),
+            (ArgType::Signed | ArgType::Unsigned | ArgType::Float, 0x05) => Ok(16),
+            (ArgType::Bool | ArgType::Signed | ArgType::Unsigned | ArgType::Float, _) => {
+                Err(ParseError::Unsupported("type length"))
+            }
+            // `Array` only marks arrays of the other types and is never an element type itself,
+            // it is only listed to keep the match exhaustive
+            (ArgType::Array, _) => Err(ParseError::UnknownArgumentType(ArgType::Array as u32)),
+            (ArgType::String, _) => Err(ParseError::UnimplementedArgumentType("string array")),
+            (ArgType::Raw, _) => Err(ParseError::UnimplementedArgumentType("raw array")),
+            (ArgType::Struct, _) => Err(ParseError::UnimplementedArgumentType("struct array")),
         }
     }
+
+    /// Reads a single `Bool`, `Signed`, `Unsigned` or `Float` value from the front of `buf`
+    fn scalar(
+        buf: &mut &'a [u8],
+        arg_type: ArgType,
+        type_length: u32,
+        msb_first: bool,
+    ) -> Result<Self, ParseError> {
+        let size = Self::scalar_size(arg_type, type_length)?;
+        ensure_remaining!(buf, size);
+
+        let value = match (arg_type, size) {
+            (ArgType::Bool, _) => Value::Bool(buf.get_u8() != 0),
+            (ArgType::Signed, 1) => Value::I8(buf.get_i8()),
+            (ArgType::Signed, 2) => Value::I16(msb!(msb_first, buf.get_i16(), buf.get_i16_le())),
+            (ArgType::Signed, 4) => Value::I32(msb!(msb_first, buf.get_i32(), buf.get_i32_le())),
+            (ArgType::Signed, 8) => Value::I64(msb!(msb_first, buf.get_i64(), buf.get_i64_le())),
+            (ArgType::Signed, _) => Value::I128(msb!(msb_first, buf.get_i128(), buf.get_i128_le())),
+            (ArgType::Unsigned, 1) => Value::U8(buf.get_u8()),
+            (ArgType::Unsigned, 2) => Value::U16(msb!(msb_first, buf.get_u16(), buf.get_u16_le())),
+            (ArgType::Unsigned, 4) => Value::U32(msb!(msb_first, buf.get_u32(), buf.get_u32_le())),
+            (ArgType::Unsigned, 8) => Value::U64(msb!(msb_first, buf.get_u64(), buf.get_u64_le())),
+            (ArgType::Unsigned, _) => {
+                Value::U128(msb!(msb_first, buf.get_u128(), buf.get_u128_le()))
+            }
+            (ArgType::Float, 2) => {
+                Value::F16(F16(msb!(msb_first, buf.get_u16(), buf.get_u16_le())))
+            }
+            (ArgType::Float, 4) => Value::F32(msb!(msb_first, buf.get_f32(), buf.get_f32_le())),
+            (ArgType::Float, 8) => Value::F64(msb!(msb_first, buf.get_f64(), buf.get_f64_le())),
+            (ArgType::Float, _) => {
+                Value::F128(F128(msb!(msb_first, buf.get_u128(), buf.get_u128_le())))
+            }
+            // `scalar_size` already rejected all other types
+            _ => return Err(ParseError::Unsupported("type length")),
+        };
+        Ok(value)
+    }
 }
 
 impl<'a> Display for Value<'a> {
@@ -358,14 +700,398 @@ impl<'a> Display for Value<'a> {
             Value::I32(i) => write!(f, "{i}"),
             Value::I64(i) => write!(f, "{i}"),
             Value::I128(i) => write!(f, "{i}"),
+            Value::F16(fl) => write!(f, "{fl}"),
             Value::F32(fl) => write!(f, "{fl:?}"),
             Value::F64(fl) => write!(f, "{fl:?}"),
+            Value::F128(fl) => write!(f, "{fl}"),
+            Value::Array(a) => write!(f, "{a}"),
             Value::String(s) => write!(f, "{s}"),
             Value::Raw(r) => r.iter().try_for_each(|byte| write!(f, "{byte:02x}")),
+            Value::Struct(s) => write!(f, "{s}"),
         }
     }
 }
 
+/// A 16 bit (half precision) float, stored as its raw bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct F16(u16);
+
+impl F16 {
+    pub fn from_bits(bits: u16) -> Self {
+        Self(bits)
+    }
+
+    pub fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    /// Converts to a `f64`, which can represent every `f16` exactly
+    pub fn to_f64(self) -> f64 {
+        let sign = (self.0 >> 15) as u64;
+        let exponent = ((self.0 >> 10) & 0x1f) as u64;
+        let mantissa = (self.0 & 0x3ff) as u64;
+
+        let bits = match exponent {
+            // zero and subnormal numbers
+            0 => {
+                let value = mantissa as f64 * 2f64.powi(-24);
+                return if sign == 1 { -value } else { value };
+            }
+            // infinity and NaN
+            0x1f => (sign << 63) | (0x7ff << 52) | (mantissa << 42),
+            _ => (sign << 63) | ((exponent + 1023 - 15) << 52) | (mantissa << 42),
+        };
+        f64::from_bits(bits)
+    }
+}
+
+impl Display for F16 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.to_f64())
+    }
+}
+
+/// A 128 bit (quadruple precision) float, stored as its raw bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct F128(u128);
+
+impl F128 {
+    pub fn from_bits(bits: u128) -> Self {
+        Self(bits)
+    }
+
+    pub fn to_bits(self) -> u128 {
+        self.0
+    }
+
+    /// Converts to the nearest `f64`, values outside of the range of `f64` become zero or infinity
+    pub fn to_f64(self) -> f64 {
+        let sign = (self.0 >> 127) as u64;
+        let exponent = ((self.0 >> 112) & 0x7fff) as i32;
+        let mantissa = self.0 & ((1 << 112) - 1);
+
+        let value = match exponent {
+            // zero and subnormal numbers, which are all far too small for a f64
+            0 => 0.0,
+            // infinity and NaN
+            0x7fff => {
+                f64::from_bits((0x7ff << 52) | (mantissa >> 60) as u64 | (mantissa != 0) as u64)
+            }
+            _ => {
+                let exponent = exponent - 16383;
+                if exponent > 1023 {
+                    f64::INFINITY
+                } else if exponent >= -1022 {
+                    // round to nearest, a carry out of the mantissa correctly increments the exponent
+                    let bits = ((exponent + 1023) as u64) << 52 | (mantissa >> 60) as u64;
+                    f64::from_bits(bits + ((mantissa >> 59) & 1) as u64)
+                } else {
+                    // a subnormal f64 (or zero)
+                    let normalized = f64::from_bits((1023 << 52) | (mantissa >> 60) as u64);
+                    normalized * 2f64.powi(exponent)
+                }
+            }
+        };
+        if sign == 1 {
+            -value
+        } else {
+            value
+        }
+    }
+}
+
+impl Display for F128 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.to_f64())
+    }
+}
+
+/// A (possibly multidimensional) array of `Bool`, `Signed`, `Unsigned` or `Float` values.
+/// The elements are stored in row-major order and are only decoded when iterating over them.
+#[derive(Debug, Clone)]
+pub struct Array<'a> {
+    element_type: ArgType,
+    type_length: u32,
+    dimensions: &'a [u8],
+    name: Option<Cow<'a, str>>,
+    unit: Option<Cow<'a, str>>,
+    fixed_point: Option<FixedPoint>,
+    data: &'a [u8],
+    msb_first: bool,
+}
+
+impl<'a> Array<'a> {
+    fn from_buf(
+        buf: &mut &'a [u8],
+        element_type: ArgType,
+        type_length: u32,
+        var_info: bool,
+        fixed_point: bool,
+        msb_first: bool,
+        string_decoding: StringDecoding,
+    ) -> Result<Self, ParseError> {
+        ensure_remaining!(buf, 2);
+        let number_of_dimensions = msb!(msb_first, buf.get_u16(), buf.get_u16_le()) as usize;
+        // every dimension adds a level of recursion when displaying the array
+        if number_of_dimensions > Argument::MAX_DEPTH {
+            return Err(ParseError::Unsupported("number of array dimensions"));
+        }
+        // without dimensions there would be a single element, which is not an array
+        if number_of_dimensions == 0 {
+            return Err(ParseError::Unsupported("array without dimensions"));
+        }
+        let dimensions = get_slice!(buf, number_of_dimensions * 2);
+
+        let (name, unit) = if var_info {
+            read_var_info(buf, true, msb_first, string_decoding)?
+        } else {
+            (None, None)
+        };
+
+        let fixed_point = if fixed_point {
+            Some(FixedPoint::from_buf(buf, type_length, msb_first)?)
+        } else {
+            None
+        };
+
+        let element_size = Value::scalar_size(element_type, type_length)?;
+        let data_length = dimensions
+            .chunks_exact(2)
+            .map(|dim| Self::dimension(dim, msb_first) as usize)
+            .try_fold(element_size, usize::checked_mul)
+            .ok_or(ParseError::Unsupported("array size"))?;
+        let data = get_slice!(buf, data_length);
+
+        Ok(Self {
+            element_type,
+            type_length,
+            dimensions,
+            name,
+            unit,
+            fixed_point,
+            data,
+            msb_first,
+        })
+    }
+
+    #[inline]
+    fn dimension(bytes: &[u8], msb_first: bool) -> u16 {
+        if msb_first {
+            u16::from_be_bytes([bytes[0], bytes[1]])
+        } else {
+            u16::from_le_bytes([bytes[0], bytes[1]])
+        }
+    }
+
+    /// The number of entries in each dimension
+    pub fn dimensions(&self) -> impl Iterator<Item = u16> + 'a {
+        let msb_first = self.msb_first;
+        self.dimensions
+            .chunks_exact(2)
+            .map(move |dim| Self::dimension(dim, msb_first))
+    }
+
+    pub fn element_type(&self) -> ArgType {
+        self.element_type
+    }
+
+    /// The quantization and offset shared by all elements of a fixed point array
+    pub fn fixed_point(&self) -> Option<FixedPoint> {
+        self.fixed_point
+    }
+
+    /// The total number of elements across all dimensions
+    pub fn len(&self) -> usize {
+        // the product can only overflow if one of the later dimensions is zero,
+        // otherwise it was already validated against the size of the data
+        self.dimensions()
+            .try_fold(1usize, |len, dim| len.checked_mul(dim as usize))
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over all elements in row-major order
+    pub fn elements(&self) -> Elements<'a> {
+        Elements {
+            data: self.data,
+            element_type: self.element_type,
+            type_length: self.type_length,
+            msb_first: self.msb_first,
+        }
+    }
+
+    fn fmt_dimensions(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        dimensions: &[u8],
+        elements: &mut Elements<'a>,
+    ) -> std::fmt::Result {
+        if dimensions.len() < 2 {
+            return Ok(());
+        }
+        let (outer, inner) = dimensions.split_at(2);
+        write!(f, "[")?;
+        for i in 0..Self::dimension(outer, self.msb_first) {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            if inner.is_empty() {
+                if let Some(element) = elements.next() {
+                    write!(f, "{element}")?;
+                }
+            } else {
+                self.fmt_dimensions(f, inner, elements)?;
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+impl<'a> Display for Array<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // don't iterate over (potentially huge) dimensions that contain no elements at all
+        if self.is_empty() {
+            return write!(f, "[]");
+        }
+        self.fmt_dimensions(f, self.dimensions, &mut self.elements())
+    }
+}
+
+/// An iterator over the elements of an [`Array`]
+#[derive(Debug, Clone)]
+pub struct Elements<'a> {
+    data: &'a [u8],
+    element_type: ArgType,
+    type_length: u32,
+    msb_first: bool,
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Value<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the size of `data` was validated when the array was parsed
+        Value::scalar(
+            &mut self.data,
+            self.element_type,
+            self.type_length,
+            self.msb_first,
+        )
+        .ok()
+    }
+}
+
+/// A struct made up of nested arguments, which can themselves be arrays or structs.
+/// The entries are only decoded when iterating over them.
+#[derive(Debug, Clone)]
+pub struct Struct<'a> {
+    name: Option<Cow<'a, str>>,
+    number_of_entries: u16,
+    data: &'a [u8],
+    msb_first: bool,
+    string_decoding: StringDecoding,
+    depth: usize,
+}
+
+impl<'a> Struct<'a> {
+    fn from_buf(
+        buf: &mut &'a [u8],
+        var_info: bool,
+        msb_first: bool,
+        string_decoding: StringDecoding,
+        depth: usize,
+    ) -> Result<Self, ParseError> {
+        ensure_remaining!(buf, 2);
+        let number_of_entries = msb!(msb_first, buf.get_u16(), buf.get_u16_le());
+
+        let (name, _) = if var_info {
+            read_var_info(buf, false, msb_first, string_decoding)?
+        } else {
+            (None, None)
+        };
+
+        if depth >= Argument::MAX_DEPTH {
+            return Err(ParseError::Unsupported("struct nesting depth"));
+        }
+
+        // the entries have to be parsed once to find out where the struct ends
+        let mut entries = Arguments {
+            data: buf,
+            index: 0,
+            msb_first,
+            string_decoding,
+            depth: depth + 1,
+            fatal: false,
+        };
+        for _ in 0..number_of_entries {
+            match entries.next() {
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err),
+                None => {
+                    return Err(ParseError::NotEnoughData {
+                        needed: Argument::MIN_LENGTH,
+                        available: 0,
+                    })
+                }
+            }
+        }
+        let data = get_slice!(buf, entries.index);
+
+        Ok(Self {
+            name,
+            number_of_entries,
+            data,
+            msb_first,
+            string_decoding,
+            depth,
+        })
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The number of (top level) entries in this struct
+    pub fn len(&self) -> usize {
+        self.number_of_entries as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.number_of_entries == 0
+    }
+
+    /// Iterates over the entries of this struct
+    pub fn entries(&self) -> Arguments<'a> {
+        Arguments {
+            data: self.data,
+            index: 0,
+            msb_first: self.msb_first,
+            string_decoding: self.string_decoding,
+            depth: self.depth + 1,
+            fatal: false,
+        }
+    }
+}
+
+impl<'a> Display for Struct<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{")?;
+        for (i, entry) in self.entries().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match entry {
+                Ok(entry) => write!(f, "{entry}")?,
+                Err(_) => write!(f, "ARGERROR")?,
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
 #[rustfmt::skip]
 #[derive(Debug, Clone, Copy)]
 #[repr(u32)]
@@ -402,8 +1128,452 @@ impl TryFrom<u32> for ArgType {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// The coding declared in the type info of a string argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StringCoding {
+    /// Strictly speaking ASCII, but in practice often ISO 8859-1 (Latin-1)
     Ascii,
     Utf8,
 }
+
+impl TryFrom<u32> for StringCoding {
+    type Error = ParseError;
+
+    fn try_from(type_info: u32) -> Result<Self, Self::Error> {
+        match type_info & TypeInfoMask::StringCoding as u32 {
+            0b00000000000000000000000000000000 => Ok(Self::Ascii),
+            0b00000000000000001000000000000000 => Ok(Self::Utf8),
+            _ => Err(ParseError::Unsupported("string coding")),
+        }
+    }
+}
+
+impl StringCoding {
+    /// Decodes `bytes` without the trailing null terminator.
+    /// ASCII strings are decoded as Latin-1, so they never fail to decode.
+    fn decode(
+        self,
+        bytes: &[u8],
+        string_decoding: StringDecoding,
+    ) -> Result<Cow<'_, str>, ParseError> {
+        let end = bytes
+            .iter()
+            .rposition(|&byte| byte != 0)
+            .map_or(0, |last| last + 1);
+        let bytes = &bytes[..end];
+
+        match (self, from_utf8(bytes)) {
+            (StringCoding::Ascii, Ok(s)) if s.is_ascii() => Ok(Cow::Borrowed(s)),
+            // every byte above 0x7f is the Latin-1 character of the same code point,
+            // even if the bytes happen to be valid UTF-8 as well
+            (StringCoding::Ascii, _) => {
+                Ok(Cow::Owned(bytes.iter().map(|&byte| byte as char).collect()))
+            }
+            (StringCoding::Utf8, Ok(s)) => Ok(Cow::Borrowed(s)),
+            (StringCoding::Utf8, Err(err)) => match string_decoding {
+                StringDecoding::Strict => Err(err.into()),
+                StringDecoding::Lossy => Ok(String::from_utf8_lossy(bytes)),
+            },
+        }
+    }
+}
+
+/// How string arguments, variable names, units and trace infos that are not valid UTF-8 are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StringDecoding {
+    /// Invalid UTF-8 is an error, which ends the iteration over the arguments
+    #[default]
+    Strict,
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`
+    Lossy,
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn array_one_dimension() {
+        // ARAY | UINT | 16 bit, 1 dimension with 3 entries
+        let bytes = b"\x42\x01\x00\x00\x01\x00\x03\x00\x01\x00\x02\x00\x03\x00";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+
+        let Value::Array(array) = arg.value() else {
+            panic!("expected an array, got {:?}", arg.value());
+        };
+        assert_eq!(array.dimensions().collect::<Vec<_>>(), [3]);
+        assert_eq!(array.len(), 3);
+        assert!(matches!(
+            array.elements().collect::<Vec<_>>()[..],
+            [Value::U16(1), Value::U16(2), Value::U16(3)]
+        ));
+        assert_eq!(arg.to_string(), "[1, 2, 3]");
+    }
+
+    #[test]
+    fn array_multiple_dimensions() {
+        // ARAY | SINT | 8 bit, 2x3 entries
+        let bytes = b"\x21\x01\x00\x00\x02\x00\x02\x00\x03\x00\x01\x02\x03\xfc\xfb\xfa";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        assert_eq!(arg.to_string(), "[[1, 2, 3], [-4, -5, -6]]");
+    }
+
+    #[test]
+    fn array_with_var_info() {
+        // ARAY | VARI | FLOA | 32 bit, 1 dimension with 2 entries
+        let bytes = b"\x83\x09\x00\x00\x01\x00\x02\x00\x04\x00\x02\x00pos\x00m\x00\x00\x00\x80\x3f\x00\x00\x00\x40";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        assert_eq!(arg.name(), Some("pos"));
+        assert_eq!(arg.unit(), Some("m"));
+        assert_eq!(arg.value().to_string(), "[1.0, 2.0]");
+    }
+
+    #[test]
+    fn array_not_enough_data() {
+        let bytes = b"\x42\x01\x00\x00\x01\x00\x03\x00\x01\x00\x02\x00";
+        assert!(matches!(
+            Argument::new(bytes, false, StringDecoding::Strict),
+            Err(ParseError::NotEnoughData { .. })
+        ));
+    }
+
+    #[test]
+    fn array_without_dimensions() {
+        // ARAY | UINT | 8 bit, 0 dimensions followed by a single value
+        let bytes = b"\x41\x01\x00\x00\x00\x00\x07";
+        assert!(matches!(
+            Argument::new(bytes, false, StringDecoding::Strict),
+            Err(ParseError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn arguments_after_array() {
+        // an 8 bit unsigned array with 2 entries followed by a bool
+        let bytes = b"\x41\x01\x00\x00\x01\x00\x02\x00\x07\x08\x11\x00\x00\x00\x01";
+        let payload = VerbosePayload::new(bytes, false);
+        assert_eq!(payload.to_string(), "[7, 8] true ");
+    }
+
+    #[test]
+    fn struct_with_name() {
+        // STRU | VARI, 2 entries named "pt": an 8 bit unsigned and a 16 bit unsigned array
+        let bytes = b"\x00\x48\x00\x00\x02\x00\x03\x00pt\x00\
+                      \x41\x00\x00\x00\x07\
+                      \x42\x01\x00\x00\x01\x00\x02\x00\x01\x00\x02\x00";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        assert_eq!(arg.name(), Some("pt"));
+
+        let Value::Struct(r#struct) = arg.value() else {
+            panic!("expected a struct, got {:?}", arg.value());
+        };
+        assert_eq!(r#struct.len(), 2);
+        let entries = r#struct.entries().collect::<Result<Vec<_>, _>>().unwrap();
+        assert!(matches!(entries[0].value(), Value::U8(7)));
+        assert!(matches!(entries[1].value(), Value::Array(_)));
+        assert_eq!(arg.to_string(), "pt={7, [1, 2]}");
+    }
+
+    #[test]
+    fn nested_structs() {
+        // a struct containing a struct containing a bool, followed by an 8 bit unsigned
+        let bytes = b"\x00\x40\x00\x00\x01\x00\
+                      \x00\x40\x00\x00\x01\x00\
+                      \x11\x00\x00\x00\x01\
+                      \x41\x00\x00\x00\x2a";
+        let payload = VerbosePayload::new(bytes, false);
+        assert_eq!(payload.to_string(), "{{true}} 42 ");
+    }
+
+    #[test]
+    fn struct_missing_entries() {
+        // a struct announcing 2 entries, but only containing 1
+        let bytes = b"\x00\x40\x00\x00\x02\x00\x11\x00\x00\x00\x01";
+        assert!(matches!(
+            Argument::new(bytes, false, StringDecoding::Strict),
+            Err(ParseError::NotEnoughData { .. })
+        ));
+    }
+
+    #[test]
+    fn var_info_numeric() {
+        // UINT | VARI | 32 bit named "speed" in "km/h"
+        let bytes = b"\x43\x08\x00\x00\x06\x00\x05\x00speed\x00km/h\x00\x2a\x00\x00\x00";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        assert_eq!(arg.name(), Some("speed"));
+        assert_eq!(arg.unit(), Some("km/h"));
+        assert!(matches!(arg.value(), Value::U32(42)));
+        assert_eq!(arg.to_string(), "speed=42 km/h");
+    }
+
+    #[test]
+    fn var_info_bool_and_string() {
+        // BOOL | VARI named "on", followed by STRG | VARI named "msg"
+        let bytes = b"\x11\x08\x00\x00\x03\x00on\x00\x01\
+                      \x00\x0a\x00\x00\x03\x00\x04\x00msg\x00hi\x00";
+        let mut arguments = VerbosePayload::new(bytes, false).arguments();
+
+        let on = arguments.next().unwrap().unwrap();
+        assert_eq!(on.name(), Some("on"));
+        assert_eq!(on.unit(), None);
+        assert_eq!(on.to_string(), "on=true");
+
+        let msg = arguments.next().unwrap().unwrap();
+        assert_eq!(msg.name(), Some("msg"));
+        assert_eq!(msg.to_string(), "msg=hi");
+
+        assert!(arguments.next().is_none());
+    }
+
+    #[test]
+    fn var_info_struct_entries() {
+        // a struct with a named 8 bit signed entry and an unnamed bool
+        let bytes = b"\x00\x40\x00\x00\x02\x00\
+                      \x21\x08\x00\x00\x02\x00\x01\x00x\x00\x00\xff\
+                      \x11\x00\x00\x00\x00";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        assert_eq!(arg.to_string(), "{x=-1, false}");
+    }
+
+    #[test]
+    fn fixed_point_32_bit_offset() {
+        // UINT | FIXP | 16 bit, quantization 0.5, offset -10, raw value 100
+        let bytes = b"\x42\x10\x00\x00\x00\x00\x00\x3f\xf6\xff\xff\xff\x64\x00";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        assert!(matches!(arg.value(), Value::U16(100)));
+        assert_eq!(
+            arg.fixed_point(),
+            Some(FixedPoint {
+                quantization: 0.5,
+                offset: -10
+            })
+        );
+        assert_eq!(arg.physical_value(), Some(40.0));
+    }
+
+    #[test]
+    fn fixed_point_64_bit_offset() {
+        // SINT | VARI | FIXP | 64 bit named "t" in "s", quantization 2.0, offset 1, raw value -3
+        let bytes = b"\x24\x18\x00\x00\x02\x00\x02\x00t\x00s\x00\x00\x00\x00\x40\
+                      \x01\x00\x00\x00\x00\x00\x00\x00\xfd\xff\xff\xff\xff\xff\xff\xff";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        assert_eq!(arg.name(), Some("t"));
+        assert_eq!(arg.physical_value(), Some(-5.0));
+    }
+
+    #[test]
+    fn fixed_point_array() {
+        // ARAY | FIXP | UINT | 8 bit with 2 entries, quantization 0.25, offset 0
+        let bytes = b"\x41\x11\x00\x00\x01\x00\x02\x00\x00\x00\x80\x3e\x00\x00\x00\x00\x04\x08";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        // the physical value is only defined for single values
+        assert_eq!(arg.physical_value(), None);
+
+        let Value::Array(array) = arg.value() else {
+            panic!("expected an array, got {:?}", arg.value());
+        };
+        let fixed_point = array.fixed_point().unwrap();
+        let physical: Vec<_> = array
+            .elements()
+            .filter_map(|raw| fixed_point.physical_value(&raw))
+            .collect();
+        assert_eq!(physical, [1.0, 2.0]);
+    }
+
+    #[test]
+    fn fixed_point_float() {
+        // FLOA | FIXP | 32 bit is not a valid combination
+        let bytes = b"\x83\x10\x00\x00\x00\x00\x80\x3f";
+        assert!(matches!(
+            Argument::new(bytes, false, StringDecoding::Strict),
+            Err(ParseError::Unsupported("fixed point"))
+        ));
+    }
+
+    #[test]
+    fn f16() {
+        assert_eq!(F16::from_bits(0x3c00).to_f64(), 1.0);
+        assert_eq!(F16::from_bits(0xc100).to_f64(), -2.5);
+        assert_eq!(F16::from_bits(0x7bff).to_f64(), 65504.0);
+        assert_eq!(F16::from_bits(0x0001).to_f64(), 2f64.powi(-24));
+        assert_eq!(F16::from_bits(0x7c00).to_f64(), f64::INFINITY);
+        assert!(F16::from_bits(0x7e00).to_f64().is_nan());
+
+        // FLOA | 16 bit
+        let bytes = b"\x82\x00\x00\x00\x00\x3e";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        assert!(matches!(arg.value(), Value::F16(f) if f.to_bits() == 0x3e00));
+        assert_eq!(arg.to_string(), "1.5");
+    }
+
+    #[test]
+    fn f128() {
+        assert_eq!(F128::from_bits(0x3fff << 112).to_f64(), 1.0);
+        assert_eq!(F128::from_bits(1 << 127 | 0x3ffe << 112).to_f64(), -0.5);
+        assert_eq!(F128::from_bits(0x7fff << 112).to_f64(), f64::INFINITY);
+        assert!(F128::from_bits(0x7fff << 112 | 1).to_f64().is_nan());
+        // too large and too small for a f64
+        assert_eq!(F128::from_bits(0x43ff << 112).to_f64(), f64::INFINITY);
+        assert_eq!(F128::from_bits(0x0001 << 112).to_f64(), 0.0);
+
+        // FLOA | 128 bit, 3.0
+        let mut bytes = b"\x85\x00\x00\x00".to_vec();
+        bytes.extend_from_slice(&(0x4000u128 << 112 | 1 << 111).to_le_bytes());
+        let arg = Argument::new(&bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        assert_eq!(arg.to_string(), "3.0");
+    }
+
+    #[test]
+    fn trace_info() {
+        // TRAI | STRG with trace info "mod::fn", followed by a bool without trace info
+        let bytes = b"\x00\x22\x00\x00\x08\x00mod::fn\x00\x06\x00enter\x00\
+                      \x11\x00\x00\x00\x01";
+        let mut arguments = VerbosePayload::new(bytes, false).arguments();
+
+        let arg = arguments.next().unwrap().unwrap();
+        assert_eq!(arg.trace_info(), Some("mod::fn"));
+        assert!(matches!(arg.value(), Value::String(s) if s == "enter"));
+        assert_eq!(arg.to_string(), "[mod::fn] enter");
+
+        let arg = arguments.next().unwrap().unwrap();
+        assert_eq!(arg.trace_info(), None);
+        assert!(matches!(arg.value(), Value::Bool(true)));
+        assert!(arguments.next().is_none());
+    }
+
+    #[test]
+    fn string_coding_ascii_latin1() {
+        // STRG | ASCII containing "caf\xe9" in Latin-1
+        let bytes = b"\x00\x02\x00\x00\x05\x00caf\xe9\x00";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.len(), bytes.len());
+        assert_eq!(arg.string_coding(), Some(StringCoding::Ascii));
+        assert!(matches!(arg.value(), Value::String(Cow::Owned(s)) if s == "caf\u{e9}"));
+
+        // Latin-1 text that is valid UTF-8 as well
+        let bytes = b"\x00\x02\x00\x00\x03\x00\xc3\xa9\x00";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.value().to_string(), "\u{c3}\u{a9}");
+        // pure ASCII is not copied
+        let bytes = b"\x00\x02\x00\x00\x04\x00caf\x00";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert!(matches!(arg.value(), Value::String(Cow::Borrowed("caf"))));
+    }
+
+    #[test]
+    fn string_coding_utf8() {
+        // STRG | UTF-8 containing "caf\u{e9}"
+        let bytes = b"\x00\x82\x00\x00\x06\x00caf\xc3\xa9\x00";
+        let arg = Argument::new(bytes, false, StringDecoding::Strict).unwrap();
+        assert_eq!(arg.string_coding(), Some(StringCoding::Utf8));
+        assert!(matches!(
+            arg.value(),
+            Value::String(Cow::Borrowed("caf\u{e9}"))
+        ));
+    }
+
+    #[test]
+    fn string_decoding_lossy() {
+        // STRG | UTF-8 containing an invalid byte, followed by a bool
+        let bytes = b"\x00\x82\x00\x00\x04\x00a\xffb\x00\x11\x00\x00\x00\x01";
+        let mut payload = VerbosePayload::new(bytes, false);
+
+        let mut strict = payload.arguments();
+        assert!(matches!(strict.next(), Some(Err(ParseError::BadUTF8(_)))));
+        assert!(strict.next().is_none());
+
+        payload.set_string_decoding(StringDecoding::Lossy);
+        assert_eq!(payload.to_string(), "a\u{fffd}b true ");
+    }
+
+    #[test]
+    fn string_decoding_lossy_var_info_and_trace_info() {
+        // TRAI | VARI | UINT | 32 bit with invalid UTF-8 in the trace info, name and unit
+        let bytes =
+            b"\x43\x28\x00\x00\x03\x00t\xff\x00\x02\x00\x03\x00\xff\x00m\xff\x00\x2a\x00\x00\x00";
+        assert!(matches!(
+            Argument::new(bytes, false, StringDecoding::Strict),
+            Err(ParseError::BadUTF8(_))
+        ));
+
+        let arg = Argument::new(bytes, false, StringDecoding::Lossy).unwrap();
+        assert_eq!(arg.trace_info(), Some("t\u{fffd}"));
+        assert_eq!(arg.name(), Some("\u{fffd}"));
+        assert_eq!(arg.unit(), Some("m\u{fffd}"));
+        assert_eq!(arg.to_string(), "[t\u{fffd}] \u{fffd}=42 m\u{fffd}");
+    }
+
+    #[test]
+    fn big_endian_corpus() {
+        // (expected, little endian, big endian)
+        let corpus: &[(&str, &[u8], &[u8])] = &[
+            (
+                "speed=42 km/h",
+                b"\x43\x08\x00\x00\x06\x00\x05\x00speed\x00km/h\x00\x2a\x00\x00\x00",
+                b"\x00\x00\x08\x43\x00\x06\x00\x05speed\x00km/h\x00\x00\x00\x00\x2a",
+            ),
+            (
+                "[1, 2, 3]",
+                b"\x42\x01\x00\x00\x01\x00\x03\x00\x01\x00\x02\x00\x03\x00",
+                b"\x00\x00\x01\x42\x00\x01\x00\x03\x00\x01\x00\x02\x00\x03",
+            ),
+            (
+                "hi",
+                b"\x00\x82\x00\x00\x03\x00hi\x00",
+                b"\x00\x00\x82\x00\x00\x03hi\x00",
+            ),
+            (
+                "abcd",
+                b"\x00\x04\x00\x00\x02\x00\xab\xcd",
+                b"\x00\x00\x04\x00\x00\x02\xab\xcd",
+            ),
+            (
+                "pt={-2}",
+                b"\x00\x48\x00\x00\x01\x00\x03\x00pt\x00\x22\x00\x00\x00\xfe\xff",
+                b"\x00\x00\x48\x00\x00\x01\x00\x03pt\x00\x00\x00\x00\x22\xff\xfe",
+            ),
+            (
+                "100",
+                b"\x42\x10\x00\x00\x00\x00\x00\x3f\xf6\xff\xff\xff\x64\x00",
+                b"\x00\x00\x10\x42\x3f\x00\x00\x00\xff\xff\xff\xf6\x00\x64",
+            ),
+            (
+                "[mod::fn] enter",
+                b"\x00\x22\x00\x00\x08\x00mod::fn\x00\x06\x00enter\x00",
+                b"\x00\x00\x22\x00\x00\x08mod::fn\x00\x00\x06enter\x00",
+            ),
+            (
+                "1.5",
+                b"\x84\x00\x00\x00\x00\x00\x00\x00\x00\x00\xf8\x3f",
+                b"\x00\x00\x00\x84\x3f\xf8\x00\x00\x00\x00\x00\x00",
+            ),
+            (
+                "1.5",
+                b"\x82\x00\x00\x00\x00\x3e",
+                b"\x00\x00\x00\x82\x3e\x00",
+            ),
+        ];
+
+        for (expected, little_endian, big_endian) in corpus {
+            let le = Argument::new(little_endian, false, StringDecoding::Strict).unwrap();
+            let be = Argument::new(big_endian, true, StringDecoding::Strict).unwrap();
+            assert_eq!(le.to_string(), *expected);
+            assert_eq!(be.to_string(), *expected);
+            assert_eq!(le.len(), little_endian.len());
+            assert_eq!(be.len(), big_endian.len());
+            assert_eq!(le.physical_value(), be.physical_value());
+        }
+    }
+}
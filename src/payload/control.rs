@@ -0,0 +1,1044 @@
+//! Control messages, which request a service of the DLT daemon or carry its response.
+//!
+//! The payload of a control message starts with the service id, a response continues with a status.
+//! What follows depends on the service, see the "Control Messages" section of the PRS.
+
+use std::{fmt::Display, str};
+
+use bytes::Buf;
+use simdutf8::basic::from_utf8;
+
+use crate::{ensure_remaining, error::ParseError, get_str, header::ControlInfo};
+
+/// The id of the service a control message is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ServiceId {
+    SetLogLevel = 0x01,
+    SetTraceStatus = 0x02,
+    GetLogInfo = 0x03,
+    GetDefaultLogLevel = 0x04,
+    StoreConfiguration = 0x05,
+    ResetToFactoryDefault = 0x06,
+    SetComInterfaceStatus = 0x07,
+    SetComInterfaceMaxBandwidth = 0x08,
+    SetVerboseMode = 0x09,
+    SetMessageFiltering = 0x0a,
+    SetTimingPackets = 0x0b,
+    GetLocalTime = 0x0c,
+    UseEcuId = 0x0d,
+    UseSessionId = 0x0e,
+    UseTimestamp = 0x0f,
+    UseExtendedHeader = 0x10,
+    SetDefaultLogLevel = 0x11,
+    SetDefaultTraceStatus = 0x12,
+    GetSoftwareVersion = 0x13,
+    MessageBufferOverflow = 0x14,
+    GetDefaultTraceStatus = 0x15,
+    GetLogChannelNames = 0x17,
+    GetTraceStatus = 0x1f,
+    SetLogChannelAssignment = 0x20,
+    SetLogChannelThreshold = 0x21,
+    GetLogChannelThreshold = 0x22,
+    BufferOverflowNotification = 0x23,
+    SyncTimeStamp = 0x24,
+    /// Only sent by the dlt-daemon, when an application unregisters one of its contexts
+    UnregisterContext = 0xf01,
+    /// Only sent by the dlt-daemon, when a client connects or disconnects
+    ConnectionInfo = 0xf02,
+    /// Only sent by the dlt-daemon
+    Timezone = 0xf03,
+    /// Only sent by the dlt-daemon, when a marker was requested
+    Marker = 0xf04,
+    Unknown(u32),
+}
+
+impl From<u32> for ServiceId {
+    fn from(service_id: u32) -> Self {
+        match service_id {
+            0x01 => Self::SetLogLevel,
+            0x02 => Self::SetTraceStatus,
+            0x03 => Self::GetLogInfo,
+            0x04 => Self::GetDefaultLogLevel,
+            0x05 => Self::StoreConfiguration,
+            0x06 => Self::ResetToFactoryDefault,
+            0x07 => Self::SetComInterfaceStatus,
+            0x08 => Self::SetComInterfaceMaxBandwidth,
+            0x09 => Self::SetVerboseMode,
+            0x0a => Self::SetMessageFiltering,
+            0x0b => Self::SetTimingPackets,
+            0x0c => Self::GetLocalTime,
+            0x0d => Self::UseEcuId,
+            0x0e => Self::UseSessionId,
+            0x0f => Self::UseTimestamp,
+            0x10 => Self::UseExtendedHeader,
+            0x11 => Self::SetDefaultLogLevel,
+            0x12 => Self::SetDefaultTraceStatus,
+            0x13 => Self::GetSoftwareVersion,
+            0x14 => Self::MessageBufferOverflow,
+            0x15 => Self::GetDefaultTraceStatus,
+            0x17 => Self::GetLogChannelNames,
+            0x1f => Self::GetTraceStatus,
+            0x20 => Self::SetLogChannelAssignment,
+            0x21 => Self::SetLogChannelThreshold,
+            0x22 => Self::GetLogChannelThreshold,
+            0x23 => Self::BufferOverflowNotification,
+            0x24 => Self::SyncTimeStamp,
+            0xf01 => Self::UnregisterContext,
+            0xf02 => Self::ConnectionInfo,
+            0xf03 => Self::Timezone,
+            0xf04 => Self::Marker,
+            service_id => Self::Unknown(service_id),
+        }
+    }
+}
+
+impl Display for ServiceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the same names as in the dlt-viewer
+        let name = match self {
+            Self::SetLogLevel => "set_log_level",
+            Self::SetTraceStatus => "set_trace_status",
+            Self::GetLogInfo => "get_log_info",
+            Self::GetDefaultLogLevel => "get_default_log_level",
+            Self::StoreConfiguration => "store_config",
+            Self::ResetToFactoryDefault => "reset_to_factory_default",
+            Self::SetComInterfaceStatus => "set_com_interface_status",
+            Self::SetComInterfaceMaxBandwidth => "set_com_interface_max_bandwidth",
+            Self::SetVerboseMode => "set_verbose_mode",
+            Self::SetMessageFiltering => "set_message_filtering",
+            Self::SetTimingPackets => "set_timing_packets",
+            Self::GetLocalTime => "get_local_time",
+            Self::UseEcuId => "use_ecu_id",
+            Self::UseSessionId => "use_session_id",
+            Self::UseTimestamp => "use_timestamp",
+            Self::UseExtendedHeader => "use_extended_header",
+            Self::SetDefaultLogLevel => "set_default_log_level",
+            Self::SetDefaultTraceStatus => "set_default_trace_status",
+            Self::GetSoftwareVersion => "get_software_version",
+            Self::MessageBufferOverflow => "message_buffer_overflow",
+            Self::GetDefaultTraceStatus => "get_default_trace_status",
+            Self::GetLogChannelNames => "get_log_channel_names",
+            Self::GetTraceStatus => "get_trace_status",
+            Self::SetLogChannelAssignment => "set_log_channel_assignment",
+            Self::SetLogChannelThreshold => "set_log_channel_threshold",
+            Self::GetLogChannelThreshold => "get_log_channel_threshold",
+            Self::BufferOverflowNotification => "buffer_overflow_notification",
+            Self::SyncTimeStamp => "sync_time_stamp",
+            Self::UnregisterContext => "unregister_context",
+            Self::ConnectionInfo => "connection_info",
+            Self::Timezone => "timezone",
+            Self::Marker => "marker",
+            Self::Unknown(service_id) => return write!(f, "service({service_id:#x})"),
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The status every response starts with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ControlStatus {
+    Ok = 0x0,
+    NotSupported = 0x1,
+    Error = 0x2,
+    /// Any other status, e.g. the kind of content of a `GetLogInfo` response
+    Other(u8),
+}
+
+impl From<u8> for ControlStatus {
+    fn from(status: u8) -> Self {
+        match status {
+            0x0 => Self::Ok,
+            0x1 => Self::NotSupported,
+            0x2 => Self::Error,
+            status => Self::Other(status),
+        }
+    }
+}
+
+impl Display for ControlStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "ok"),
+            Self::NotSupported => write!(f, "not_supported"),
+            Self::Error => write!(f, "error"),
+            Self::Other(status) => write!(f, "{status}"),
+        }
+    }
+}
+
+/// The payload of a control message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlPayload<'a> {
+    Request(ControlRequest<'a>),
+    Response(ControlResponse<'a>),
+}
+
+impl<'a> ControlPayload<'a> {
+    /// Parses the `data` following the `service_id` of a control request or response.
+    ///
+    /// Returns `None` for control messages that are neither, as their content is not specified.
+    pub fn from_slice(
+        info: ControlInfo,
+        service_id: u32,
+        data: &'a [u8],
+        msb_first: bool,
+    ) -> Option<Result<Self, ParseError>> {
+        let service_id = ServiceId::from(service_id);
+        match info {
+            ControlInfo::Request => {
+                Some(ControlRequest::from_slice(service_id, data).map(ControlPayload::Request))
+            }
+            ControlInfo::Response => Some(
+                ControlResponse::from_slice(service_id, data, msb_first)
+                    .map(ControlPayload::Response),
+            ),
+            ControlInfo::Time | ControlInfo::Unknown(_) => None,
+        }
+    }
+
+    pub fn service_id(&self) -> ServiceId {
+        match self {
+            ControlPayload::Request(request) => request.service_id(),
+            ControlPayload::Response(response) => response.service_id(),
+        }
+    }
+}
+
+impl<'a> Display for ControlPayload<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControlPayload::Request(request) => write!(f, "{request}"),
+            ControlPayload::Response(response) => write!(f, "{response}"),
+        }
+    }
+}
+
+/// A request for a service of the DLT daemon.
+///
+/// Log levels and trace status use `-1` to fall back to the default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlRequest<'a> {
+    SetLogLevel {
+        application_id: &'a str,
+        context_id: &'a str,
+        log_level: i8,
+        com_interface: &'a str,
+    },
+    SetTraceStatus {
+        application_id: &'a str,
+        context_id: &'a str,
+        trace_status: i8,
+        com_interface: &'a str,
+    },
+    GetLogInfo {
+        options: u8,
+        application_id: &'a str,
+        context_id: &'a str,
+        com_interface: &'a str,
+    },
+    GetDefaultLogLevel,
+    StoreConfiguration,
+    ResetToFactoryDefault,
+    SetMessageFiltering {
+        enabled: bool,
+    },
+    SetDefaultLogLevel {
+        log_level: i8,
+        com_interface: &'a str,
+    },
+    SetDefaultTraceStatus {
+        trace_status: i8,
+        com_interface: &'a str,
+    },
+    GetSoftwareVersion,
+    GetDefaultTraceStatus,
+    GetLogChannelNames,
+    GetTraceStatus {
+        application_id: &'a str,
+        context_id: &'a str,
+    },
+    SetLogChannelAssignment {
+        application_id: &'a str,
+        context_id: &'a str,
+        log_channel: &'a str,
+        /// `true` to add the context to the log channel, `false` to remove it
+        add: bool,
+    },
+    SetLogChannelThreshold {
+        log_channel: &'a str,
+        log_level: i8,
+        trace_status: i8,
+    },
+    GetLogChannelThreshold {
+        log_channel: &'a str,
+    },
+    BufferOverflowNotification,
+    /// A service without any specified request content
+    Other {
+        service_id: ServiceId,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> ControlRequest<'a> {
+    pub fn from_slice(service_id: ServiceId, mut buf: &'a [u8]) -> Result<Self, ParseError> {
+        Ok(match service_id {
+            ServiceId::SetLogLevel => ControlRequest::SetLogLevel {
+                application_id: read_id(&mut buf)?,
+                context_id: read_id(&mut buf)?,
+                log_level: read_i8(&mut buf)?,
+                com_interface: read_id(&mut buf)?,
+            },
+            ServiceId::SetTraceStatus => ControlRequest::SetTraceStatus {
+                application_id: read_id(&mut buf)?,
+                context_id: read_id(&mut buf)?,
+                trace_status: read_i8(&mut buf)?,
+                com_interface: read_id(&mut buf)?,
+            },
+            ServiceId::GetLogInfo => ControlRequest::GetLogInfo {
+                options: read_u8(&mut buf)?,
+                application_id: read_id(&mut buf)?,
+                context_id: read_id(&mut buf)?,
+                com_interface: read_id(&mut buf)?,
+            },
+            ServiceId::GetDefaultLogLevel => ControlRequest::GetDefaultLogLevel,
+            ServiceId::StoreConfiguration => ControlRequest::StoreConfiguration,
+            ServiceId::ResetToFactoryDefault => ControlRequest::ResetToFactoryDefault,
+            ServiceId::SetMessageFiltering => ControlRequest::SetMessageFiltering {
+                enabled: read_u8(&mut buf)? != 0,
+            },
+            ServiceId::SetDefaultLogLevel => ControlRequest::SetDefaultLogLevel {
+                log_level: read_i8(&mut buf)?,
+                com_interface: read_id(&mut buf)?,
+            },
+            ServiceId::SetDefaultTraceStatus => ControlRequest::SetDefaultTraceStatus {
+                trace_status: read_i8(&mut buf)?,
+                com_interface: read_id(&mut buf)?,
+            },
+            ServiceId::GetSoftwareVersion => ControlRequest::GetSoftwareVersion,
+            ServiceId::GetDefaultTraceStatus => ControlRequest::GetDefaultTraceStatus,
+            ServiceId::GetLogChannelNames => ControlRequest::GetLogChannelNames,
+            ServiceId::GetTraceStatus => ControlRequest::GetTraceStatus {
+                application_id: read_id(&mut buf)?,
+                context_id: read_id(&mut buf)?,
+            },
+            ServiceId::SetLogChannelAssignment => ControlRequest::SetLogChannelAssignment {
+                application_id: read_id(&mut buf)?,
+                context_id: read_id(&mut buf)?,
+                log_channel: read_id(&mut buf)?,
+                add: read_u8(&mut buf)? != 0,
+            },
+            ServiceId::SetLogChannelThreshold => ControlRequest::SetLogChannelThreshold {
+                log_channel: read_id(&mut buf)?,
+                log_level: read_i8(&mut buf)?,
+                trace_status: read_i8(&mut buf)?,
+            },
+            ServiceId::GetLogChannelThreshold => ControlRequest::GetLogChannelThreshold {
+                log_channel: read_id(&mut buf)?,
+            },
+            ServiceId::BufferOverflowNotification => ControlRequest::BufferOverflowNotification,
+            service_id => ControlRequest::Other {
+                service_id,
+                data: buf,
+            },
+        })
+    }
+
+    pub fn service_id(&self) -> ServiceId {
+        match self {
+            ControlRequest::SetLogLevel { .. } => ServiceId::SetLogLevel,
+            ControlRequest::SetTraceStatus { .. } => ServiceId::SetTraceStatus,
+            ControlRequest::GetLogInfo { .. } => ServiceId::GetLogInfo,
+            ControlRequest::GetDefaultLogLevel => ServiceId::GetDefaultLogLevel,
+            ControlRequest::StoreConfiguration => ServiceId::StoreConfiguration,
+            ControlRequest::ResetToFactoryDefault => ServiceId::ResetToFactoryDefault,
+            ControlRequest::SetMessageFiltering { .. } => ServiceId::SetMessageFiltering,
+            ControlRequest::SetDefaultLogLevel { .. } => ServiceId::SetDefaultLogLevel,
+            ControlRequest::SetDefaultTraceStatus { .. } => ServiceId::SetDefaultTraceStatus,
+            ControlRequest::GetSoftwareVersion => ServiceId::GetSoftwareVersion,
+            ControlRequest::GetDefaultTraceStatus => ServiceId::GetDefaultTraceStatus,
+            ControlRequest::GetLogChannelNames => ServiceId::GetLogChannelNames,
+            ControlRequest::GetTraceStatus { .. } => ServiceId::GetTraceStatus,
+            ControlRequest::SetLogChannelAssignment { .. } => ServiceId::SetLogChannelAssignment,
+            ControlRequest::SetLogChannelThreshold { .. } => ServiceId::SetLogChannelThreshold,
+            ControlRequest::GetLogChannelThreshold { .. } => ServiceId::GetLogChannelThreshold,
+            ControlRequest::BufferOverflowNotification => ServiceId::BufferOverflowNotification,
+            ControlRequest::Other { service_id, .. } => *service_id,
+        }
+    }
+}
+
+impl<'a> Display for ControlRequest<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}]", self.service_id())?;
+        match self {
+            ControlRequest::SetLogLevel {
+                application_id,
+                context_id,
+                log_level: level,
+                ..
+            }
+            | ControlRequest::SetTraceStatus {
+                application_id,
+                context_id,
+                trace_status: level,
+                ..
+            } => write!(f, " {application_id} {context_id} {level}"),
+            ControlRequest::GetLogInfo {
+                options,
+                application_id,
+                context_id,
+                ..
+            } => write!(f, " {options} {application_id} {context_id}"),
+            ControlRequest::SetMessageFiltering { enabled } => write!(f, " {}", *enabled as u8),
+            ControlRequest::SetDefaultLogLevel {
+                log_level: level, ..
+            }
+            | ControlRequest::SetDefaultTraceStatus {
+                trace_status: level,
+                ..
+            } => write!(f, " {level}"),
+            ControlRequest::GetTraceStatus {
+                application_id,
+                context_id,
+            } => write!(f, " {application_id} {context_id}"),
+            ControlRequest::SetLogChannelAssignment {
+                application_id,
+                context_id,
+                log_channel,
+                add,
+            } => write!(
+                f,
+                " {application_id} {context_id} {log_channel} {}",
+                if *add { "add" } else { "remove" }
+            ),
+            ControlRequest::SetLogChannelThreshold {
+                log_channel,
+                log_level,
+                trace_status,
+            } => write!(f, " {log_channel} {log_level} {trace_status}"),
+            ControlRequest::GetLogChannelThreshold { log_channel } => write!(f, " {log_channel}"),
+            ControlRequest::Other { data, .. } => fmt_data(f, data),
+            ControlRequest::GetDefaultLogLevel
+            | ControlRequest::StoreConfiguration
+            | ControlRequest::ResetToFactoryDefault
+            | ControlRequest::GetSoftwareVersion
+            | ControlRequest::GetDefaultTraceStatus
+            | ControlRequest::GetLogChannelNames
+            | ControlRequest::BufferOverflowNotification => Ok(()),
+        }
+    }
+}
+
+/// The response of the DLT daemon to a [`ControlRequest`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlResponse<'a> {
+    SetLogLevel {
+        status: ControlStatus,
+    },
+    SetTraceStatus {
+        status: ControlStatus,
+    },
+    /// Which fields of the contexts are set depends on the status: 3 only the ids, 4 the log level,
+    /// 5 the trace status, 6 both and 7 also the descriptions of applications and contexts
+    GetLogInfo {
+        status: ControlStatus,
+        applications: Vec<ApplicationInfo<'a>>,
+    },
+    GetDefaultLogLevel {
+        status: ControlStatus,
+        log_level: i8,
+    },
+    StoreConfiguration {
+        status: ControlStatus,
+    },
+    ResetToFactoryDefault {
+        status: ControlStatus,
+    },
+    SetMessageFiltering {
+        status: ControlStatus,
+    },
+    SetDefaultLogLevel {
+        status: ControlStatus,
+    },
+    SetDefaultTraceStatus {
+        status: ControlStatus,
+    },
+    GetSoftwareVersion {
+        status: ControlStatus,
+        version: &'a str,
+    },
+    MessageBufferOverflow {
+        status: ControlStatus,
+        overflow: bool,
+    },
+    GetDefaultTraceStatus {
+        status: ControlStatus,
+        trace_status: i8,
+    },
+    GetLogChannelNames {
+        status: ControlStatus,
+        log_channels: Vec<&'a str>,
+    },
+    GetTraceStatus {
+        status: ControlStatus,
+        trace_status: i8,
+    },
+    SetLogChannelAssignment {
+        status: ControlStatus,
+    },
+    SetLogChannelThreshold {
+        status: ControlStatus,
+    },
+    GetLogChannelThreshold {
+        status: ControlStatus,
+        log_level: i8,
+        trace_status: i8,
+    },
+    BufferOverflowNotification {
+        status: ControlStatus,
+        overflow_counter: u32,
+    },
+    UnregisterContext {
+        status: ControlStatus,
+        application_id: &'a str,
+        context_id: &'a str,
+        com_interface: &'a str,
+    },
+    ConnectionInfo {
+        status: ControlStatus,
+        connected: bool,
+        com_interface: &'a str,
+    },
+    Timezone {
+        status: ControlStatus,
+        /// The offset to UTC in seconds
+        timezone: i32,
+        is_dst: bool,
+    },
+    Marker {
+        status: ControlStatus,
+    },
+    /// A service without any specified response content
+    Other {
+        service_id: ServiceId,
+        status: ControlStatus,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> ControlResponse<'a> {
+    pub fn from_slice(
+        service_id: ServiceId,
+        mut buf: &'a [u8],
+        msb_first: bool,
+    ) -> Result<Self, ParseError> {
+        let status = ControlStatus::from(read_u8(&mut buf)?);
+        // errors never carry any content
+        if matches!(status, ControlStatus::NotSupported | ControlStatus::Error) {
+            return Ok(ControlResponse::Other {
+                service_id,
+                status,
+                data: buf,
+            });
+        }
+
+        Ok(match service_id {
+            ServiceId::SetLogLevel => ControlResponse::SetLogLevel { status },
+            ServiceId::SetTraceStatus => ControlResponse::SetTraceStatus { status },
+            ServiceId::GetLogInfo => ControlResponse::GetLogInfo {
+                status,
+                applications: ApplicationInfo::from_response(&mut buf, status, msb_first)?,
+            },
+            ServiceId::GetDefaultLogLevel => ControlResponse::GetDefaultLogLevel {
+                status,
+                log_level: read_i8(&mut buf)?,
+            },
+            ServiceId::StoreConfiguration => ControlResponse::StoreConfiguration { status },
+            ServiceId::ResetToFactoryDefault => ControlResponse::ResetToFactoryDefault { status },
+            ServiceId::SetMessageFiltering => ControlResponse::SetMessageFiltering { status },
+            ServiceId::SetDefaultLogLevel => ControlResponse::SetDefaultLogLevel { status },
+            ServiceId::SetDefaultTraceStatus => ControlResponse::SetDefaultTraceStatus { status },
+            ServiceId::GetSoftwareVersion => {
+                let length = read_u32(&mut buf, msb_first)? as usize;
+                ControlResponse::GetSoftwareVersion {
+                    status,
+                    version: get_str!(buf, length)?.trim_end_matches('\0'),
+                }
+            }
+            ServiceId::MessageBufferOverflow => ControlResponse::MessageBufferOverflow {
+                status,
+                overflow: read_u8(&mut buf)? != 0,
+            },
+            ServiceId::GetDefaultTraceStatus => ControlResponse::GetDefaultTraceStatus {
+                status,
+                trace_status: read_i8(&mut buf)?,
+            },
+            ServiceId::GetLogChannelNames => {
+                let count = read_u8(&mut buf)?;
+                ControlResponse::GetLogChannelNames {
+                    status,
+                    log_channels: (0..count)
+                        .map(|_| read_id(&mut buf))
+                        .collect::<Result<_, _>>()?,
+                }
+            }
+            ServiceId::GetTraceStatus => ControlResponse::GetTraceStatus {
+                status,
+                trace_status: read_i8(&mut buf)?,
+            },
+            ServiceId::SetLogChannelAssignment => {
+                ControlResponse::SetLogChannelAssignment { status }
+            }
+            ServiceId::SetLogChannelThreshold => ControlResponse::SetLogChannelThreshold { status },
+            ServiceId::GetLogChannelThreshold => ControlResponse::GetLogChannelThreshold {
+                status,
+                log_level: read_i8(&mut buf)?,
+                trace_status: read_i8(&mut buf)?,
+            },
+            ServiceId::BufferOverflowNotification => ControlResponse::BufferOverflowNotification {
+                status,
+                overflow_counter: read_u32(&mut buf, msb_first)?,
+            },
+            ServiceId::UnregisterContext => ControlResponse::UnregisterContext {
+                status,
+                application_id: read_id(&mut buf)?,
+                context_id: read_id(&mut buf)?,
+                com_interface: read_id(&mut buf)?,
+            },
+            ServiceId::ConnectionInfo => ControlResponse::ConnectionInfo {
+                status,
+                // 1 means disconnected and 2 connected
+                connected: read_u8(&mut buf)? == 2,
+                com_interface: read_id(&mut buf)?,
+            },
+            ServiceId::Timezone => ControlResponse::Timezone {
+                status,
+                timezone: read_u32(&mut buf, msb_first)? as i32,
+                is_dst: read_u8(&mut buf)? != 0,
+            },
+            ServiceId::Marker => ControlResponse::Marker { status },
+            service_id => ControlResponse::Other {
+                service_id,
+                status,
+                data: buf,
+            },
+        })
+    }
+
+    pub fn service_id(&self) -> ServiceId {
+        match self {
+            ControlResponse::SetLogLevel { .. } => ServiceId::SetLogLevel,
+            ControlResponse::SetTraceStatus { .. } => ServiceId::SetTraceStatus,
+            ControlResponse::GetLogInfo { .. } => ServiceId::GetLogInfo,
+            ControlResponse::GetDefaultLogLevel { .. } => ServiceId::GetDefaultLogLevel,
+            ControlResponse::StoreConfiguration { .. } => ServiceId::StoreConfiguration,
+            ControlResponse::ResetToFactoryDefault { .. } => ServiceId::ResetToFactoryDefault,
+            ControlResponse::SetMessageFiltering { .. } => ServiceId::SetMessageFiltering,
+            ControlResponse::SetDefaultLogLevel { .. } => ServiceId::SetDefaultLogLevel,
+            ControlResponse::SetDefaultTraceStatus { .. } => ServiceId::SetDefaultTraceStatus,
+            ControlResponse::GetSoftwareVersion { .. } => ServiceId::GetSoftwareVersion,
+            ControlResponse::MessageBufferOverflow { .. } => ServiceId::MessageBufferOverflow,
+            ControlResponse::GetDefaultTraceStatus { .. } => ServiceId::GetDefaultTraceStatus,
+            ControlResponse::GetLogChannelNames { .. } => ServiceId::GetLogChannelNames,
+            ControlResponse::GetTraceStatus { .. } => ServiceId::GetTraceStatus,
+            ControlResponse::SetLogChannelAssignment { .. } => ServiceId::SetLogChannelAssignment,
+            ControlResponse::SetLogChannelThreshold { .. } => ServiceId::SetLogChannelThreshold,
+            ControlResponse::GetLogChannelThreshold { .. } => ServiceId::GetLogChannelThreshold,
+            ControlResponse::BufferOverflowNotification { .. } => {
+                ServiceId::BufferOverflowNotification
+            }
+            ControlResponse::UnregisterContext { .. } => ServiceId::UnregisterContext,
+            ControlResponse::ConnectionInfo { .. } => ServiceId::ConnectionInfo,
+            ControlResponse::Timezone { .. } => ServiceId::Timezone,
+            ControlResponse::Marker { .. } => ServiceId::Marker,
+            ControlResponse::Other { service_id, .. } => *service_id,
+        }
+    }
+
+    pub fn status(&self) -> ControlStatus {
+        match self {
+            ControlResponse::SetLogLevel { status }
+            | ControlResponse::SetTraceStatus { status }
+            | ControlResponse::GetLogInfo { status, .. }
+            | ControlResponse::GetDefaultLogLevel { status, .. }
+            | ControlResponse::StoreConfiguration { status }
+            | ControlResponse::ResetToFactoryDefault { status }
+            | ControlResponse::SetMessageFiltering { status }
+            | ControlResponse::SetDefaultLogLevel { status }
+            | ControlResponse::SetDefaultTraceStatus { status }
+            | ControlResponse::GetSoftwareVersion { status, .. }
+            | ControlResponse::MessageBufferOverflow { status, .. }
+            | ControlResponse::GetDefaultTraceStatus { status, .. }
+            | ControlResponse::GetLogChannelNames { status, .. }
+            | ControlResponse::GetTraceStatus { status, .. }
+            | ControlResponse::SetLogChannelAssignment { status }
+            | ControlResponse::SetLogChannelThreshold { status }
+            | ControlResponse::GetLogChannelThreshold { status, .. }
+            | ControlResponse::BufferOverflowNotification { status, .. }
+            | ControlResponse::UnregisterContext { status, .. }
+            | ControlResponse::ConnectionInfo { status, .. }
+            | ControlResponse::Timezone { status, .. }
+            | ControlResponse::Marker { status }
+            | ControlResponse::Other { status, .. } => *status,
+        }
+    }
+}
+
+impl<'a> Display for ControlResponse<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{} {}]", self.service_id(), self.status())?;
+        match self {
+            ControlResponse::GetLogInfo { applications, .. } => applications
+                .iter()
+                .try_for_each(|application| write!(f, " {application}")),
+            ControlResponse::Other { data, .. } => fmt_data(f, data),
+            ControlResponse::GetDefaultLogLevel {
+                log_level: level, ..
+            }
+            | ControlResponse::GetDefaultTraceStatus {
+                trace_status: level,
+                ..
+            }
+            | ControlResponse::GetTraceStatus {
+                trace_status: level,
+                ..
+            } => write!(f, " {level}"),
+            ControlResponse::GetSoftwareVersion { version, .. } => write!(f, " {version}"),
+            ControlResponse::MessageBufferOverflow { overflow, .. } => {
+                write!(f, " {}", *overflow as u8)
+            }
+            ControlResponse::GetLogChannelNames { log_channels, .. } => log_channels
+                .iter()
+                .try_for_each(|log_channel| write!(f, " {log_channel}")),
+            ControlResponse::GetLogChannelThreshold {
+                log_level,
+                trace_status,
+                ..
+            } => write!(f, " {log_level} {trace_status}"),
+            ControlResponse::BufferOverflowNotification {
+                overflow_counter, ..
+            } => write!(f, " {overflow_counter}"),
+            ControlResponse::UnregisterContext {
+                application_id,
+                context_id,
+                com_interface,
+                ..
+            } => write!(f, " {application_id} {context_id} {com_interface}"),
+            ControlResponse::ConnectionInfo {
+                connected,
+                com_interface,
+                ..
+            } => write!(
+                f,
+                " {} {com_interface}",
+                if *connected {
+                    "connected"
+                } else {
+                    "disconnected"
+                }
+            ),
+            ControlResponse::Timezone {
+                timezone, is_dst, ..
+            } => write!(f, " {timezone} {}", if *is_dst { "dst" } else { "no_dst" }),
+            ControlResponse::SetLogLevel { .. }
+            | ControlResponse::SetTraceStatus { .. }
+            | ControlResponse::StoreConfiguration { .. }
+            | ControlResponse::ResetToFactoryDefault { .. }
+            | ControlResponse::SetMessageFiltering { .. }
+            | ControlResponse::SetDefaultLogLevel { .. }
+            | ControlResponse::SetDefaultTraceStatus { .. }
+            | ControlResponse::SetLogChannelAssignment { .. }
+            | ControlResponse::SetLogChannelThreshold { .. }
+            | ControlResponse::Marker { .. } => Ok(()),
+        }
+    }
+}
+
+/// An application and its contexts, as registered with the DLT daemon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationInfo<'a> {
+    pub application_id: &'a str,
+    pub description: Option<&'a str>,
+    pub contexts: Vec<ContextInfo<'a>>,
+}
+
+/// A context, as registered with the DLT daemon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextInfo<'a> {
+    pub context_id: &'a str,
+    pub log_level: Option<i8>,
+    pub trace_status: Option<i8>,
+    pub description: Option<&'a str>,
+}
+
+impl<'a> ApplicationInfo<'a> {
+    /// Reads all applications of a `GetLogInfo` response.
+    ///
+    /// The status tells what is known about each context:
+    /// 3 only the ids, 4 the log level, 5 the trace status, 6 both and 7 also the descriptions.
+    /// Any other status comes without applications.
+    fn from_response(
+        buf: &mut &'a [u8],
+        status: ControlStatus,
+        msb_first: bool,
+    ) -> Result<Vec<Self>, ParseError> {
+        let ControlStatus::Other(options @ 3..=7) = status else {
+            return Ok(Vec::new());
+        };
+        let with_log_level = matches!(options, 4 | 6 | 7);
+        let with_trace_status = matches!(options, 5..=7);
+        let with_description = options == 7;
+
+        let number_of_applications = read_u16(buf, msb_first)?;
+        // every application takes up at least 6 bytes, this prevents huge allocations for bogus counts
+        let mut applications =
+            Vec::with_capacity((number_of_applications as usize).min(buf.len() / 6));
+        for _ in 0..number_of_applications {
+            let application_id = read_id(buf)?;
+            let number_of_contexts = read_u16(buf, msb_first)?;
+            let mut contexts = Vec::with_capacity((number_of_contexts as usize).min(buf.len() / 4));
+            for _ in 0..number_of_contexts {
+                let context_id = read_id(buf)?;
+                let log_level = with_log_level.then(|| read_i8(buf)).transpose()?;
+                let trace_status = with_trace_status.then(|| read_i8(buf)).transpose()?;
+                let description = with_description
+                    .then(|| read_description(buf, msb_first))
+                    .transpose()?;
+                contexts.push(ContextInfo {
+                    context_id,
+                    log_level,
+                    trace_status,
+                    description,
+                });
+            }
+            let description = with_description
+                .then(|| read_description(buf, msb_first))
+                .transpose()?;
+            applications.push(ApplicationInfo {
+                application_id,
+                description,
+                contexts,
+            });
+        }
+        // the com interface follows, which is of no interest here
+
+        Ok(applications)
+    }
+}
+
+impl<'a> Display for ApplicationInfo<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.application_id)?;
+        if let Some(description) = self.description {
+            write!(f, " ({description})")?;
+        }
+        write!(f, " [")?;
+        for (index, context) in self.contexts.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", context.context_id)?;
+            if let Some(log_level) = context.log_level {
+                write!(f, " {log_level}")?;
+            }
+            if let Some(trace_status) = context.trace_status {
+                write!(f, " {trace_status}")?;
+            }
+            if let Some(description) = context.description {
+                write!(f, " ({description})")?;
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+/// Reads a description with a 16 bit length prefix
+fn read_description<'a>(buf: &mut &'a [u8], msb_first: bool) -> Result<&'a str, ParseError> {
+    let length = read_u16(buf, msb_first)? as usize;
+    Ok(get_str!(buf, length)?.trim_end_matches('\0'))
+}
+
+/// Writes unspecified content as hex, like a non-verbose payload
+fn fmt_data(f: &mut std::fmt::Formatter<'_>, data: &[u8]) -> std::fmt::Result {
+    if !data.is_empty() {
+        write!(f, " ")?;
+    }
+    data.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+}
+
+/// Reads a 4 character id, like an application, context or log channel id
+fn read_id<'a>(buf: &mut &'a [u8]) -> Result<&'a str, ParseError> {
+    Ok(get_str!(buf, 4)?.trim_end_matches('\0'))
+}
+
+fn read_u8(buf: &mut &[u8]) -> Result<u8, ParseError> {
+    ensure_remaining!(buf, 1);
+    Ok(buf.get_u8())
+}
+
+fn read_i8(buf: &mut &[u8]) -> Result<i8, ParseError> {
+    ensure_remaining!(buf, 1);
+    Ok(buf.get_i8())
+}
+
+fn read_u16(buf: &mut &[u8], msb_first: bool) -> Result<u16, ParseError> {
+    ensure_remaining!(buf, 2);
+    Ok(if msb_first {
+        buf.get_u16()
+    } else {
+        buf.get_u16_le()
+    })
+}
+
+fn read_u32(buf: &mut &[u8], msb_first: bool) -> Result<u32, ParseError> {
+    ensure_remaining!(buf, 4);
+    Ok(if msb_first {
+        buf.get_u32()
+    } else {
+        buf.get_u32_le()
+    })
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn set_log_level_request() {
+        let payload =
+            ControlPayload::from_slice(ControlInfo::Request, 0x01, b"APP\0CTX\0\x04remo", false)
+                .unwrap()
+                .unwrap();
+        assert_eq!(
+            payload,
+            ControlPayload::Request(ControlRequest::SetLogLevel {
+                application_id: "APP",
+                context_id: "CTX",
+                log_level: 4,
+                com_interface: "remo",
+            })
+        );
+        assert_eq!(payload.to_string(), "[set_log_level] APP CTX 4");
+    }
+
+    #[test]
+    fn responses() {
+        let response = |service_id, data: &'static [u8], msb_first| {
+            ControlPayload::from_slice(ControlInfo::Response, service_id, data, msb_first)
+                .unwrap()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(response(0x01, b"\x00", false), "[set_log_level ok]");
+        assert_eq!(
+            response(0x04, b"\x00\x04", false),
+            "[get_default_log_level ok] 4"
+        );
+        assert_eq!(
+            response(0x13, b"\x00\x00\x00\x00\x0cDLT 2.18.10\0", true),
+            "[get_software_version ok] DLT 2.18.10"
+        );
+        assert_eq!(
+            response(0x17, b"\x00\x02MAINSEC\0", false),
+            "[get_log_channel_names ok] MAIN SEC"
+        );
+        assert_eq!(
+            response(0xf02, b"\x00\x02remo", false),
+            "[connection_info ok] connected remo"
+        );
+        assert_eq!(response(0x03, b"\x08", false), "[get_log_info 8]");
+        assert_eq!(response(0x42, b"\x00\xab", false), "[service(0x42) ok] ab");
+        assert_eq!(
+            response(0x13, b"\x01", false),
+            "[get_software_version not_supported]"
+        );
+    }
+
+    #[test]
+    fn get_log_info_response() {
+        let with_descriptions = b"\x07\x01\x00APP1\x02\x00\
+                                  CTX1\x04\x00\x07\x00Context\
+                                  CTX2\xff\x01\x00\x00\
+                                  \x0b\x00Application\
+                                  remo";
+        let ControlPayload::Response(response) =
+            ControlPayload::from_slice(ControlInfo::Response, 0x03, with_descriptions, false)
+                .unwrap()
+                .unwrap()
+        else {
+            panic!("expected a response");
+        };
+        let ControlResponse::GetLogInfo { applications, .. } = &response else {
+            panic!("expected a get_log_info response");
+        };
+        assert_eq!(
+            applications,
+            &[ApplicationInfo {
+                application_id: "APP1",
+                description: Some("Application"),
+                contexts: vec![
+                    ContextInfo {
+                        context_id: "CTX1",
+                        log_level: Some(4),
+                        trace_status: Some(0),
+                        description: Some("Context"),
+                    },
+                    ContextInfo {
+                        context_id: "CTX2",
+                        log_level: Some(-1),
+                        trace_status: Some(1),
+                        description: Some(""),
+                    }
+                ],
+            }]
+        );
+        assert_eq!(
+            response.to_string(),
+            "[get_log_info 7] APP1 (Application) [CTX1 4 0 (Context), CTX2 -1 1 ()]"
+        );
+
+        let without_descriptions = b"\x06\x02\x00\
+                                     APP1\x01\x00CTX1\x04\x00\
+                                     APP2\x01\x00CTX1\x02\x01\
+                                     remo";
+        assert_eq!(
+            ControlPayload::from_slice(ControlInfo::Response, 0x03, without_descriptions, false)
+                .unwrap()
+                .unwrap()
+                .to_string(),
+            "[get_log_info 6] APP1 [CTX1 4 0] APP2 [CTX1 2 1]"
+        );
+
+        assert!(matches!(
+            ControlPayload::from_slice(
+                ControlInfo::Response,
+                0x03,
+                &with_descriptions[..20],
+                false
+            ),
+            Some(Err(ParseError::NotEnoughData { .. }))
+        ));
+    }
+
+    #[test]
+    fn truncated() {
+        assert!(matches!(
+            ControlPayload::from_slice(
+                ControlInfo::Response,
+                0x13,
+                b"\x00\x20\x00\x00\x00DLT",
+                false
+            ),
+            Some(Err(ParseError::NotEnoughData { .. }))
+        ));
+        assert!(matches!(
+            ControlPayload::from_slice(ControlInfo::Request, 0x02, b"APP\0", false),
+            Some(Err(ParseError::NotEnoughData { .. }))
+        ));
+        assert!(ControlPayload::from_slice(ControlInfo::Time, 0x01, b"", false).is_none());
+    }
+}
@@ -0,0 +1,435 @@
+//! Reading messages from any [`Read`] source with constant memory.
+//!
+//! [`DltFile`](crate::DltFile) needs the whole log in memory, a [`DltReader`] only keeps a buffer
+//! that is large enough for the biggest possible message and refills it as messages are consumed.
+//! This makes it possible to process traces of any size, or messages arriving through a pipe.
+
+use std::{
+    io::{self, ErrorKind, Read},
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{
+    decoder::DecoderRegistry,
+    error::{DltError, ParseError, ReadError},
+    header::StorageHeader,
+    message::DltMessage,
+    payload::StringDecoding,
+};
+
+/// The largest possible message: a version 2 storage header with an ECU id of 255 bytes,
+/// followed by the standard header, whose 16 bit length includes everything after it
+const MAX_MESSAGE_LENGTH: usize = StorageHeader::MIN_LENGTH + u8::MAX as usize + u16::MAX as usize;
+pub(crate) const DEFAULT_CAPACITY: usize = 1 << 20;
+
+/// Reads messages from `R`, see the [module level documentation](self)
+#[derive(Debug)]
+pub struct DltReader<R> {
+    reader: R,
+    buffer: MessageBuffer,
+    decoding: Decoding,
+}
+
+impl<R: Read> DltReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    /// Creates a reader with a buffer of `capacity` bytes,
+    /// which is raised to the length of the biggest possible message if it is smaller
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            reader,
+            buffer: MessageBuffer::new(capacity),
+            decoding: Decoding::default(),
+        }
+    }
+
+    /// Sets how string arguments that are not valid UTF-8 are handled in all messages
+    pub fn with_string_decoding(mut self, string_decoding: StringDecoding) -> Self {
+        self.decoding.string_decoding = string_decoding;
+        self
+    }
+
+    /// Sets the decoders that format the payloads of all messages, see [`DltMessage::set_decoder`]
+    pub fn with_decoders(mut self, decoders: Arc<DecoderRegistry>) -> Self {
+        self.decoding.decoders = Some(decoders);
+        self
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the underlying reader, any buffered data is lost
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next message, which borrows the internal buffer until the next call.
+    ///
+    /// Malformed messages are skipped the same way as in [`DltFile`](crate::DltFile),
+    /// I/O errors are yielded as they occur and reading can be retried afterwards.
+    pub fn next_message(&mut self) -> Option<Result<DltMessage<'_>, ReadError>> {
+        let length = loop {
+            match self.buffer.next() {
+                Next::Message(length) => break length,
+                Next::Error(err) => return Some(Err(err.into())),
+                Next::Fill => {
+                    if let Err(err) = self.fill() {
+                        return Some(Err(err.into()));
+                    }
+                }
+                Next::End => return None,
+            }
+        };
+        Some(
+            DltMessage::from_slice(self.buffer.take(length))
+                .map(|mut message| {
+                    self.decoding.apply(&mut message);
+                    message
+                })
+                .map_err(ReadError::from),
+        )
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        loop {
+            match self.reader.read(self.buffer.spare()) {
+                Ok(read) => {
+                    self.buffer.filled(read);
+                    return Ok(());
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// Yields owned copies of the messages, use [`DltReader::next_message`] to avoid the copies
+impl<R: Read> Iterator for DltReader<R> {
+    type Item = Result<OwnedMessage, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let decoding = self.decoding.clone();
+        Some(
+            self.next_message()?
+                .map(|message| OwnedMessage::new(&message, decoding)),
+        )
+    }
+}
+
+/// What to do next to get a message out of a [`MessageBuffer`]
+#[derive(Debug)]
+pub(crate) enum Next {
+    /// A message of this length is buffered, see [`MessageBuffer::take`]
+    Message(usize),
+    Error(DltError),
+    /// More data has to be read into [`MessageBuffer::spare`]
+    Fill,
+    End,
+}
+
+/// The buffering and resynchronisation of messages that are read in pieces,
+/// independent of how they are read
+#[derive(Debug)]
+pub(crate) struct MessageBuffer {
+    buf: Box<[u8]>,
+    // the unconsumed data is `buf[start..end]`
+    start: usize,
+    end: usize,
+    eof: bool,
+    // the error of a malformed message, yielded once the start of the next message is found
+    resync_error: Option<DltError>,
+}
+
+impl MessageBuffer {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            buf: vec![0; capacity.max(MAX_MESSAGE_LENGTH)].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            eof: false,
+            resync_error: None,
+        }
+    }
+
+    pub(crate) fn next(&mut self) -> Next {
+        loop {
+            if self.resync_error.is_some() {
+                if self.resync() {
+                    return self.resync_error.take().map_or(Next::End, Next::Error);
+                } else if !self.eof {
+                    return Next::Fill;
+                }
+                // at the end of the input the rest is discarded, just like `DltFile` does
+                self.start = self.end;
+                self.resync_error = None;
+            }
+
+            let available = &self.buf[self.start..self.end];
+            if available.is_empty() && self.eof {
+                return Next::End;
+            }
+            // the message is only parsed completely once it is taken
+            match DltMessage::length(available) {
+                Ok(length) => return Next::Message(length),
+                // the message continues after the buffered data
+                Err(DltError {
+                    advance_by: None,
+                    source: ParseError::NotEnoughData { .. },
+                }) if !self.eof => return Next::Fill,
+                Err(err) => match err.advance_by {
+                    Some(advance_by) => {
+                        self.start += advance_by.min(available.len());
+                        return Next::Error(err);
+                    }
+                    None => {
+                        // skipping the first byte makes sure we don't find the malformed message again
+                        self.start += 1;
+                        self.resync_error = Some(err);
+                    }
+                },
+            }
+        }
+    }
+
+    /// Skips the buffered data up to the start of the next message,
+    /// returns `false` if it is not buffered yet
+    fn resync(&mut self) -> bool {
+        let available = &self.buf[self.start..self.end];
+        let version = StorageHeader::PATTERN.len();
+        let found = memchr::memmem::find_iter(available, StorageHeader::PATTERN)
+            .find(|&index| matches!(available.get(index + version), Some(0x1 | 0x2) | None));
+        match found {
+            Some(index) => {
+                self.start += index;
+                index + version < available.len()
+            }
+            // keep what could be the beginning of the pattern
+            None => {
+                self.start = self.end - available.len().min(version - 1);
+                false
+            }
+        }
+    }
+
+    /// Takes the message of `length` bytes returned by [`MessageBuffer::next`]
+    pub(crate) fn take(&mut self, length: usize) -> &[u8] {
+        let start = self.start;
+        self.start += length;
+        &self.buf[start..self.start]
+    }
+
+    /// Moves the unconsumed data to the front and returns the free space after it
+    pub(crate) fn spare(&mut self) -> &mut [u8] {
+        if self.start > 0 {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        &mut self.buf[self.end..]
+    }
+
+    /// Marks `read` bytes of [`MessageBuffer::spare`] as filled, 0 marks the end of the input
+    pub(crate) fn filled(&mut self, read: usize) {
+        if read == 0 {
+            self.eof = true;
+        }
+        self.end += read;
+    }
+}
+
+/// The settings of a reader that are applied to every message it yields
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Decoding {
+    pub(crate) string_decoding: StringDecoding,
+    pub(crate) decoders: Option<Arc<DecoderRegistry>>,
+}
+
+impl Decoding {
+    pub(crate) fn apply<'a>(&'a self, message: &mut DltMessage<'a>) {
+        message.payload.set_string_decoding(self.string_decoding);
+        if let Some(decoders) = &self.decoders {
+            message.set_decoder(decoders.decoder(message));
+        }
+    }
+}
+
+/// Registries can't be compared, so they are only equal if they are the same
+impl PartialEq for Decoding {
+    fn eq(&self, other: &Self) -> bool {
+        self.string_decoding == other.string_decoding
+            && match (&self.decoders, &other.decoders) {
+                (Some(decoders), Some(other)) => Arc::ptr_eq(decoders, other),
+                (decoders, other) => decoders.is_none() && other.is_none(),
+            }
+    }
+}
+
+impl Eq for Decoding {}
+
+/// A message that owns its data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedMessage {
+    bytes: Vec<u8>,
+    // the storage header of a message that was received without one
+    synthesized: Option<(Duration, String)>,
+    decoding: Decoding,
+}
+
+impl OwnedMessage {
+    pub(crate) fn new(message: &DltMessage<'_>, decoding: Decoding) -> Self {
+        let header = &message.storage_header;
+        Self {
+            bytes: message.as_bytes().to_vec(),
+            synthesized: header
+                .is_synthesized()
+                .then(|| (header.timestamp(), header.ecu_id.to_string())),
+            decoding,
+        }
+    }
+
+    /// Parses the message again, which only parses the headers since the payload is parsed lazily.
+    /// It has the string decoding and decoder of the reader it came from.
+    pub fn message(&self) -> DltMessage<'_> {
+        let message = match &self.synthesized {
+            Some((timestamp, ecu_id)) => DltMessage::with_storage_header(
+                StorageHeader::synthesized(*timestamp, ecu_id),
+                &self.bytes,
+            ),
+            None => DltMessage::from_slice(&self.bytes),
+        };
+        let mut message = message.expect("the bytes were already parsed into a message");
+        self.decoding.apply(&mut message);
+        message
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::DltFile;
+
+    const MESSAGE: &[u8] = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                             \x21\x00\x00\x17\
+                             \x41\x01APP1CTX1\
+                             \x00\x82\x00\x00\x03\x00hi\x00";
+
+    /// Returns at most `chunk` bytes per read, so messages straddle every possible boundary
+    struct Chunked<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    /// The length of each message or `None` for each error, which must match `DltFile`
+    fn lengths(data: &[u8], chunk: usize) -> Vec<Option<usize>> {
+        let mut reader = DltReader::new(Chunked { data, chunk });
+        let mut lengths = Vec::new();
+        while let Some(message) = reader.next_message() {
+            lengths.push(message.ok().map(|message| message.len()));
+        }
+        assert_eq!(
+            lengths,
+            DltFile::new(data)
+                .map(|message| message.ok().map(|message| message.len()))
+                .collect::<Vec<_>>()
+        );
+        lengths
+    }
+
+    #[test]
+    fn messages_across_reads() {
+        let data = [MESSAGE, MESSAGE, MESSAGE].concat();
+        for chunk in 1..data.len() {
+            assert_eq!(lengths(&data, chunk), vec![Some(MESSAGE.len()); 3]);
+        }
+
+        let messages = DltReader::new(&data[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].as_bytes(), MESSAGE);
+        assert_eq!(messages[2].message().payload.to_string(), "hi ");
+    }
+
+    #[test]
+    fn resynchronisation() {
+        let mut corrupted = MESSAGE.to_vec();
+        // a standard header length smaller than the header itself
+        corrupted[19] = 0x02;
+        let data = [
+            &b"garbage DL"[..],
+            MESSAGE,
+            &corrupted,
+            b"DLT\x03",
+            MESSAGE,
+            &MESSAGE[..20],
+        ]
+        .concat();
+        for chunk in 1..data.len() {
+            assert_eq!(
+                lengths(&data, chunk),
+                [None, Some(MESSAGE.len()), None, Some(MESSAGE.len())]
+            );
+        }
+    }
+
+    #[test]
+    fn large_messages() {
+        // a message with the biggest possible payload
+        let mut message = MESSAGE[..28].to_vec();
+        message[18..20].copy_from_slice(&u16::MAX.to_be_bytes());
+        message.resize(16 + u16::MAX as usize, 0);
+        let data = [&message[..], MESSAGE, &message].concat();
+        let mut reader = DltReader::with_capacity(0, &data[..]);
+        let mut count = 0;
+        while let Some(message) = reader.next_message() {
+            message.unwrap();
+            count += 1;
+        }
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn io_errors() {
+        struct Failing(bool);
+        impl Read for Failing {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0 = !self.0;
+                if self.0 {
+                    Err(ErrorKind::Other.into())
+                } else {
+                    Chunked {
+                        data: MESSAGE,
+                        chunk: usize::MAX,
+                    }
+                    .read(buf)
+                }
+            }
+        }
+        let mut reader = DltReader::new(Failing(false));
+        assert!(matches!(reader.next(), Some(Err(ReadError::Io(_)))));
+        assert!(matches!(reader.next(), Some(Ok(_))));
+    }
+}
@@ -0,0 +1,191 @@
+//! What is known about the applications and contexts of each ECU.
+//!
+//! The DLT daemon answers `GetLogInfo` requests with all registered applications and contexts,
+//! their log levels, trace status and descriptions. An [`EcuRegistry`] collects these responses
+//! while iterating messages, so that later messages can be annotated with the descriptions.
+
+use std::collections::HashMap;
+
+use crate::{
+    message::DltMessage,
+    payload::control::{ApplicationInfo, ControlPayload, ControlResponse},
+};
+
+/// The applications and contexts of all ECUs, as reported in `GetLogInfo` responses
+#[derive(Debug, Default, Clone)]
+pub struct EcuRegistry {
+    ecus: HashMap<String, HashMap<String, Application>>,
+}
+
+/// A registered application and its contexts
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Application {
+    pub description: Option<String>,
+    pub contexts: HashMap<String, Context>,
+}
+
+/// A registered context
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Context {
+    pub description: Option<String>,
+    pub log_level: Option<i8>,
+    pub trace_status: Option<i8>,
+}
+
+impl EcuRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the content of `message`, if it is a `GetLogInfo` response,
+    /// under the ECU that sent it, see [`DltMessage::sender_ecu_id`]
+    pub fn update(&mut self, message: &DltMessage<'_>) {
+        if let Some(Ok(ControlPayload::Response(response))) = message.control_payload() {
+            self.insert(message.sender_ecu_id(), &response);
+        }
+    }
+
+    /// Adds the content of a `GetLogInfo` response of the ECU `ecu_id`, other responses are ignored.
+    ///
+    /// Fields that are missing in the response, depending on its options, keep their previous value.
+    pub fn insert(&mut self, ecu_id: &str, response: &ControlResponse<'_>) {
+        let ControlResponse::GetLogInfo { applications, .. } = response else {
+            return;
+        };
+        let ecu = self.ecus.entry(ecu_id.to_string()).or_default();
+        for ApplicationInfo {
+            application_id,
+            description,
+            contexts,
+        } in applications
+        {
+            let application = ecu.entry(application_id.to_string()).or_default();
+            if let Some(description) = description {
+                application.description = Some(description.to_string());
+            }
+            for info in contexts {
+                let context = application
+                    .contexts
+                    .entry(info.context_id.to_string())
+                    .or_default();
+                if let Some(description) = info.description {
+                    context.description = Some(description.to_string());
+                }
+                context.log_level = info.log_level.or(context.log_level);
+                context.trace_status = info.trace_status.or(context.trace_status);
+            }
+        }
+    }
+
+    pub fn application(&self, ecu_id: &str, application_id: &str) -> Option<&Application> {
+        self.ecus.get(ecu_id)?.get(application_id)
+    }
+
+    pub fn context(
+        &self,
+        ecu_id: &str,
+        application_id: &str,
+        context_id: &str,
+    ) -> Option<&Context> {
+        self.application(ecu_id, application_id)?
+            .contexts
+            .get(context_id)
+    }
+
+    /// The description of the application that sent `message`
+    pub fn application_description(&self, message: &DltMessage<'_>) -> Option<&str> {
+        self.application(message.sender_ecu_id(), message.app_id()?)?
+            .description
+            .as_deref()
+    }
+
+    /// The description of the context that sent `message`
+    pub fn context_description(&self, message: &DltMessage<'_>) -> Option<&str> {
+        self.context(
+            message.sender_ecu_id(),
+            message.app_id()?,
+            message.context_id()?,
+        )?
+        .description
+        .as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::payload::control::{ContextInfo, ControlStatus};
+    use crate::DltFile;
+
+    #[test]
+    fn descriptions_from_get_log_info() {
+        // a get_log_info response with descriptions, followed by a log message of the described context
+        let data = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                     \x21\x00\x00\x3b\
+                     \x26\x01DA1\x00DC1\x00\
+                     \x03\x00\x00\x00\
+                     \x07\x01\x00APP1\x01\x00CTX1\x04\x00\x07\x00Context\x0b\x00Application\
+                     remo\
+                     DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                     \x21\x01\x00\x0e\
+                     \x41\x00APP1CTX1";
+        let mut registry = EcuRegistry::new();
+        let mut messages = DltFile::new(data).flatten();
+
+        registry.update(&messages.next().unwrap());
+        let message = messages.next().unwrap();
+        assert_eq!(
+            registry.application_description(&message),
+            Some("Application")
+        );
+        assert_eq!(registry.context_description(&message), Some("Context"));
+        assert_eq!(
+            registry.context("ECU", "APP1", "CTX1").unwrap().log_level,
+            Some(4)
+        );
+
+        // without descriptions the previous ones are kept
+        registry.insert(
+            "ECU",
+            &ControlResponse::GetLogInfo {
+                status: ControlStatus::Other(6),
+                applications: vec![ApplicationInfo {
+                    application_id: "APP1",
+                    description: None,
+                    contexts: vec![ContextInfo {
+                        context_id: "CTX1",
+                        log_level: Some(2),
+                        trace_status: Some(0),
+                        description: None,
+                    }],
+                }],
+            },
+        );
+        let context = registry.context("ECU", "APP1", "CTX1").unwrap();
+        assert_eq!(context.description.as_deref(), Some("Context"));
+        assert_eq!(context.log_level, Some(2));
+    }
+
+    #[test]
+    fn descriptions_of_the_sender() {
+        // the same response and log message, stored by a logger with another ECU id
+        let data = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00LOG\x00\
+                     \x25\x00\x00\x3fECU1\
+                     \x26\x01DA1\x00DC1\x00\
+                     \x03\x00\x00\x00\
+                     \x07\x01\x00APP1\x01\x00CTX1\x04\x00\x07\x00Context\x0b\x00Application\
+                     remo\
+                     DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00LOG\x00\
+                     \x25\x01\x00\x12ECU1\
+                     \x41\x00APP1CTX1";
+        let mut registry = EcuRegistry::new();
+        let mut messages = DltFile::new(data).flatten();
+
+        registry.update(&messages.next().unwrap());
+        assert!(registry.application("LOG", "APP1").is_none());
+        assert!(registry.application("ECU1", "APP1").is_some());
+        let message = messages.next().unwrap();
+        assert_eq!(registry.context_description(&message), Some("Context"));
+    }
+}
@@ -0,0 +1,157 @@
+//! Reading messages from a tokio [`AsyncRead`] source.
+
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::{
+    decoder::DecoderRegistry,
+    error::ReadError,
+    message::DltMessage,
+    payload::StringDecoding,
+    reader::{Decoding, MessageBuffer, Next, OwnedMessage, DEFAULT_CAPACITY},
+};
+
+/// The asynchronous version of a [`DltReader`](crate::DltReader).
+///
+/// Data is only read while the stream is polled and the buffer has a constant size,
+/// so a slow consumer slows down reading instead of buffering more and more.
+/// Dropping a pending `next()` loses no data, since everything that was read stays in the buffer.
+#[derive(Debug)]
+pub struct DltStream<R> {
+    reader: R,
+    buffer: MessageBuffer,
+    decoding: Decoding,
+}
+
+impl<R: AsyncRead + Unpin> DltStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, reader)
+    }
+
+    /// Creates a stream with a buffer of `capacity` bytes,
+    /// which is raised to the length of the biggest possible message if it is smaller
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        Self {
+            reader,
+            buffer: MessageBuffer::new(capacity),
+            decoding: Decoding::default(),
+        }
+    }
+
+    /// Sets how string arguments that are not valid UTF-8 are handled in all messages
+    pub fn with_string_decoding(mut self, string_decoding: StringDecoding) -> Self {
+        self.decoding.string_decoding = string_decoding;
+        self
+    }
+
+    /// Sets the decoders that format the payloads of all messages, see [`DltMessage::set_decoder`]
+    pub fn with_decoders(mut self, decoders: Arc<DecoderRegistry>) -> Self {
+        self.decoding.decoders = Some(decoders);
+        self
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the underlying reader, any buffered data is lost
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for DltStream<R> {
+    type Item = Result<OwnedMessage, ReadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match this.buffer.next() {
+                Next::Message(length) => {
+                    let message = DltMessage::from_slice(this.buffer.take(length))
+                        .map(|message| OwnedMessage::new(&message, this.decoding.clone()));
+                    return Poll::Ready(Some(message.map_err(ReadError::from)));
+                }
+                Next::Error(err) => return Poll::Ready(Some(Err(err.into()))),
+                Next::Fill => {
+                    let mut buf = ReadBuf::new(this.buffer.spare());
+                    match Pin::new(&mut this.reader).poll_read(cx, &mut buf) {
+                        Poll::Ready(Ok(())) => {
+                            let read = buf.filled().len();
+                            this.buffer.filled(read);
+                        }
+                        Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                Next::End => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::future::poll_fn;
+
+    use tokio::io::AsyncWriteExt;
+
+    use super::*;
+
+    const MESSAGE: &[u8] = b"DLT\x01\x0d\x00\x00\x00\x25\x00\x00\x00ECU\x00\
+                             \x21\x00\x00\x17\
+                             \x41\x01APP1CTX1\
+                             \x00\x82\x00\x00\x03\x00hi\x00";
+
+    async fn next<R: AsyncRead + Unpin>(
+        stream: &mut DltStream<R>,
+    ) -> Option<Result<OwnedMessage, ReadError>> {
+        poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    #[tokio::test]
+    async fn messages_across_writes() {
+        let (mut writer, reader) = tokio::io::duplex(8);
+        let data = [&b"garbage"[..], MESSAGE, MESSAGE].concat();
+        let writing = tokio::spawn(async move {
+            for chunk in data.chunks(5) {
+                writer.write_all(chunk).await.unwrap();
+            }
+        });
+
+        let mut stream = DltStream::new(reader);
+        assert!(matches!(
+            next(&mut stream).await,
+            Some(Err(ReadError::Dlt(_)))
+        ));
+        for _ in 0..2 {
+            let message = next(&mut stream).await.unwrap().unwrap();
+            assert_eq!(message.as_bytes(), MESSAGE);
+            assert_eq!(message.message().payload.to_string(), "hi ");
+        }
+        writing.await.unwrap();
+        assert!(next(&mut stream).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn cancelled_polls() {
+        let (mut writer, reader) = tokio::io::duplex(64);
+        let mut stream = DltStream::new(reader);
+
+        writer.write_all(&MESSAGE[..20]).await.unwrap();
+        // the first half is buffered while waiting for the rest
+        let timeout = tokio::time::timeout(std::time::Duration::from_millis(10), next(&mut stream));
+        assert!(timeout.await.is_err());
+
+        writer.write_all(&MESSAGE[20..]).await.unwrap();
+        let message = next(&mut stream).await.unwrap().unwrap();
+        assert_eq!(message.as_bytes(), MESSAGE);
+    }
+}
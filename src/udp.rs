@@ -0,0 +1,209 @@
+//! Receiving messages that are sent over UDP, to a unicast or multicast address.
+//!
+//! Each datagram carries one or more messages without storage headers, so each message gets
+//! a synthesized one with the time it was received. Messages without an ECU id in their standard
+//! header are attributed to an ECU by the address of their sender.
+
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    decoder::DecoderRegistry,
+    error::ReadError,
+    message::DltMessage,
+    payload::StringDecoding,
+    reader::{Decoding, OwnedMessage},
+};
+
+const MAX_DATAGRAM_SIZE: usize = 1 << 16;
+
+/// Receives messages from a UDP socket, see the [module level documentation](self)
+#[derive(Debug)]
+pub struct DltUdpReceiver {
+    socket: UdpSocket,
+    buf: Box<[u8]>,
+    // the unparsed messages of the last datagram are `buf[start..end]`
+    start: usize,
+    end: usize,
+    source: Option<SocketAddr>,
+    received: Duration,
+    ecu_ids: HashMap<IpAddr, String>,
+    ecu_id: String,
+    decoding: Decoding,
+}
+
+impl DltUdpReceiver {
+    /// Receives datagrams sent to `address`, e.g. `("0.0.0.0", 3490)`
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        UdpSocket::bind(address).map(Self::from_socket)
+    }
+
+    /// Receives datagrams with a socket that was set up elsewhere, e.g. to reuse its address
+    pub fn from_socket(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            buf: vec![0; MAX_DATAGRAM_SIZE].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            source: None,
+            received: Duration::ZERO,
+            ecu_ids: HashMap::new(),
+            ecu_id: String::new(),
+            decoding: Decoding::default(),
+        }
+    }
+
+    /// Joins the multicast `group` on the interface with the address `interface`,
+    /// `Ipv4Addr::UNSPECIFIED` lets the OS choose one
+    pub fn join_multicast_v4(&self, group: Ipv4Addr, interface: Ipv4Addr) -> io::Result<()> {
+        self.socket.join_multicast_v4(&group, &interface)
+    }
+
+    /// Joins the multicast `group` on the interface with the index `interface`, 0 lets the OS choose one
+    pub fn join_multicast_v6(&self, group: Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.socket.join_multicast_v6(&group, interface)
+    }
+
+    /// Sets the ECU id of messages from `source` without one in their standard header
+    pub fn with_source_ecu_id(mut self, source: IpAddr, ecu_id: &str) -> Self {
+        self.ecu_ids.insert(source, ecu_id.to_string());
+        self
+    }
+
+    /// Sets the ECU id of messages without one in their standard header from all other sources
+    pub fn with_ecu_id(mut self, ecu_id: &str) -> Self {
+        self.ecu_id = ecu_id.to_string();
+        self
+    }
+
+    /// Sets how string arguments that are not valid UTF-8 are handled in all messages
+    pub fn with_string_decoding(mut self, string_decoding: StringDecoding) -> Self {
+        self.decoding.string_decoding = string_decoding;
+        self
+    }
+
+    /// Sets the decoders that format the payloads of all messages, see [`DltMessage::set_decoder`]
+    pub fn with_decoders(mut self, decoders: Arc<DecoderRegistry>) -> Self {
+        self.decoding.decoders = Some(decoders);
+        self
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// The sender of the last received datagram, which the last message was part of
+    pub fn source(&self) -> Option<SocketAddr> {
+        self.source
+    }
+
+    /// Receives the next message, which borrows the receiver until the next call.
+    /// This blocks until a datagram arrives, unless the socket was set to be non-blocking.
+    ///
+    /// A malformed message is yielded as an error, the rest of its datagram is skipped.
+    pub fn next_message(&mut self) -> Result<DltMessage<'_>, ReadError> {
+        while self.start >= self.end {
+            self.receive()?;
+        }
+        let ecu_id = self
+            .source
+            .and_then(|source| self.ecu_ids.get(&source.ip()))
+            .unwrap_or(&self.ecu_id);
+        match DltMessage::from_raw_slice(&self.buf[self.start..self.end], self.received, ecu_id) {
+            Ok(mut message) => {
+                self.start += message.len();
+                self.decoding.apply(&mut message);
+                Ok(message)
+            }
+            Err(err) => {
+                self.start = match err.advance_by {
+                    Some(advance_by) => (self.start + advance_by).min(self.end),
+                    None => self.end,
+                };
+                Err(err.into())
+            }
+        }
+    }
+
+    fn receive(&mut self) -> io::Result<()> {
+        let (read, source) = loop {
+            match self.socket.recv_from(&mut self.buf) {
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+        self.received = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        self.source = Some(source);
+        self.start = 0;
+        self.end = read;
+        Ok(())
+    }
+}
+
+/// Yields owned copies of the messages, use [`DltUdpReceiver::next_message`] to avoid the copies.
+/// This never ends, unless the socket was set to be non-blocking.
+impl Iterator for DltUdpReceiver {
+    type Item = Result<OwnedMessage, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let decoding = self.decoding.clone();
+        match self.next_message() {
+            Ok(message) => Some(Ok(OwnedMessage::new(&message, decoding))),
+            Err(ReadError::Io(err)) if err.kind() == ErrorKind::WouldBlock => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    // verbose log messages without storage header, the first one with an ECU id in the standard header
+    const WITH_ECU_ID: &[u8] = b"\x25\x00\x00\x1bECU1\
+                                 \x41\x01APP1CTX1\
+                                 \x00\x82\x00\x00\x03\x00hi\x00";
+    const WITHOUT_ECU_ID: &[u8] = b"\x21\x00\x00\x17\
+                                    \x41\x01APP1CTX1\
+                                    \x00\x82\x00\x00\x03\x00hi\x00";
+
+    #[test]
+    fn messages_per_datagram() {
+        let mut receiver = DltUdpReceiver::bind("127.0.0.1:0")
+            .unwrap()
+            .with_source_ecu_id(Ipv4Addr::LOCALHOST.into(), "LOC")
+            .with_ecu_id("ANY");
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = receiver.socket().local_addr().unwrap();
+
+        sender
+            .send_to(
+                &[WITH_ECU_ID, WITHOUT_ECU_ID, b"\x21\x00"].concat(),
+                address,
+            )
+            .unwrap();
+        sender.send_to(WITHOUT_ECU_ID, address).unwrap();
+
+        let message = receiver.next_message().unwrap();
+        assert_eq!(message.ecu_id(), "ECU1");
+        assert!(message.storage_header.is_synthesized());
+        assert_eq!(message.as_bytes(), WITH_ECU_ID);
+        assert_eq!(receiver.next_message().unwrap().ecu_id(), "LOC");
+        assert_eq!(receiver.source(), Some(sender.local_addr().unwrap()));
+        // the truncated message at the end of the first datagram
+        assert!(matches!(receiver.next_message(), Err(ReadError::Dlt(_))));
+
+        receiver.socket().set_nonblocking(true).unwrap();
+        let messages = receiver.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message().payload.to_string(), "hi ");
+    }
+}